@@ -0,0 +1,36 @@
+use std::fs;
+use std::path::Path;
+use anyhow::Context as _;
+use crate::config::{Config, InstallRoot};
+use crate::error::{Error, Result};
+use crate::events;
+use crate::project::Project;
+
+// Gradle packages whatever is in src/main/assets
+const ASSETS_DIR: &str = "android/app/src/main/assets";
+
+/// Copy the configured resources into the gradle project's assets; `$DOC` goes to `doc/`.
+/// There is no place for extra executables or system configuration in an APK, so `$BIN` and
+/// `$ETC` resources are skipped.
+pub fn copy_resources() -> Result<()> {
+    let project = Project::load().map_err(Error::Config)?;
+    let config = Config::for_package(&project, project.package()).map_err(Error::Config)?;
+    for resource in &config.resources {
+        let dest = match resource.root {
+            InstallRoot::Data => Path::new(ASSETS_DIR).join(&resource.path),
+            InstallRoot::Doc => Path::new(ASSETS_DIR).join("doc").join(&resource.path),
+            InstallRoot::Bin | InstallRoot::Etc => {
+                events::warning(format_args!(
+                    "skipping {}: {} resources are not supported on Android",
+                    resource.source.display(),
+                    resource.root.var()
+                ));
+                continue;
+            }
+        };
+        fs::create_dir_all(dest.parent().unwrap())?;
+        fs::copy(&resource.source, &dest)
+            .with_context(|| format!("failed to copy {}", resource.source.display()))?;
+    }
+    Ok(())
+}
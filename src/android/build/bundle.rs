@@ -1,26 +1,54 @@
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::Command;
-pub fn begin_gradle_build() {
-    println!("Beginning gradle build");
+use crate::error::{Error, Result};
+use crate::events::{self, status, Event};
 
-    let batch_file = r"android\\gradlew.bat build --project-dir android";
-    match Command::new("cmd")
-        .args(&["/C", batch_file])
-        // .arg("build")
-        // .arg("--project-dir")
-        // .arg("android")
-        
+/// Run the gradle build and report the produced APKs/AABs; returns how many were found.
+pub fn begin_gradle_build() -> Result<usize> {
+    status!("Beginning gradle build");
+    events::emit(&Event::BundleStarted {
+        package: "android",
+        format: "apk",
+        target: "android",
+    });
+
+    // The wrapper `android init` writes; the Unix script may lack its executable bit
+    let mut gradle = if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", r"android\gradlew.bat"]);
+        cmd
+    } else {
+        let mut sh = Command::new("sh");
+        sh.arg("android/gradlew");
+        sh
+    };
+    let status = gradle
+        .args(["build", "--project-dir", "android"])
+        .stdout(events::child_stdout())
         .status()
-    {
-        Ok(status) => {
-            if status.success() {
-                println!("Gradle build completed successfully");
-            } else {
-                match status.code() {
-                    Some(code) => eprintln!("Gradle build failed with exit code: {}", code),
-                    None => eprintln!("Gradle build failed and was terminated by signal"),
-                }
-            }
+        .map_err(|e| Error::Build(format!("failed to spawn gradle process: {}", e)))?;
+    if !status.success() {
+        return Err(Error::Build(format!("gradle build failed ({})", status)));
+    }
+    status!("Gradle build completed successfully");
+
+    let mut outputs = Vec::new();
+    find_outputs(Path::new("android/app/build/outputs"), &mut outputs);
+    for path in &outputs {
+        let format = path.extension().and_then(|e| e.to_str()).unwrap_or("apk");
+        events::artifact_created("android", format, "android", "android", path);
+    }
+    Ok(outputs.len())
+}
+
+fn find_outputs(dir: &Path, outputs: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for path in entries.flatten().map(|e| e.path()) {
+        if path.is_dir() {
+            find_outputs(&path, outputs);
+        } else if matches!(path.extension().and_then(|e| e.to_str()), Some("apk" | "aab")) {
+            outputs.push(path);
         }
-        Err(err) => eprintln!("Failed to spawn gradle process: {}", err),
     }
-}
\ No newline at end of file
+}
@@ -1,36 +1,40 @@
 use std::process::Command;
+use crate::error::{Error, Result};
+use crate::events::{self, status, Event};
 
+pub const TARGETS: [&str; 4] = [
+    "armv7-linux-androideabi",
+    "aarch64-linux-android",
+    "i686-linux-android",
+    "x86_64-linux-android",
+];
 
-/* targets
-armv7-linux-androideabi
-aarch64-linux-android
-i686-linux-android
-x86_644-linux-android
-*/
-
-pub fn build_android_targets(mode: &str) -> Result<(), std::io::Error> {
-    let targets = vec![
-        "armv7-linux-androideabi",
-        "aarch64-linux-android",
-        "i686-linux-android",
-        "x86_64-linux-android",
-    ];
-    for target in targets {
+/// Build the library for every Android ABI; a failing ABI doesn't stop the others, and all
+/// failures are returned together.
+pub fn build_android_targets(mode: &str) -> Result<()> {
+    let mut failures = Vec::new();
+    for target in TARGETS {
+        status!("Building for target: {}", target);
+        let mut cargo = Command::new("cargo");
+        cargo.args(["build", "--lib", "--target", target]);
         if mode == "--release" {
-            println!("Building for target: {}", target);
-            let build_args = ["build", "--lib", mode, "--target", target];
-            let _ = Command::new("cargo")
-                .args(build_args)
-                .status();
+            cargo.arg(mode);
         }
-        else {
-            println!("Building for target: {}", target);
-            let build_args = ["build", "--lib", "--target", target];
-            let _ = Command::new("cargo")
-                .args(build_args)
-                .status();
+        let success = match cargo.stdout(events::child_stdout()).status() {
+            Ok(status) => status.success(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(Error::missing_tool("cargo")),
+            Err(e) => return Err(e.into()),
+        };
+        events::emit(&Event::CargoFinished { target, success });
+        if !success {
+            events::warning(format_args!("cargo build failed for {}", target));
+            failures.push(Error::Build(format!("cargo build failed for {}", target)));
         }
     }
 
-    Ok(())
-}
\ No newline at end of file
+    match failures.len() {
+        0 => Ok(()),
+        1 => Err(failures.remove(0)),
+        _ => Err(Error::Bundles(failures)),
+    }
+}
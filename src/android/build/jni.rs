@@ -1,49 +1,14 @@
 use std::fs;
 use std::path::Path;
-use std::env;
-use toml::Value;
+use anyhow::{bail, Context as _, Result};
+use crate::project::Project;
 
 
 
 // target/aarch64-linux-android/
 
-fn get_lib_name(mut mode: &str) -> Option<String> {
-    // Try to find an actual .so built in any of the Android target release folders first
-    let project_root = env::current_dir().ok()?;
-    let triples = [
-        "aarch64-linux-android",
-        "armv7-linux-androideabi",
-        "i686-linux-android",
-        "x86_64-linux-android",
-    ];
-    if mode == "--release" {
-        mode = "release";
-    }
-    else {
-        mode = "debug";
-    }
-
-    for t in &triples {
-        let dir = project_root.join("target").join(t).join(mode);
-        if dir.is_dir() {
-            if let Ok(entries) = fs::read_dir(&dir) {
-                for entry in entries.flatten() {
-                    let path = entry.path();
-                    if path.extension().and_then(|s| s.to_str()) == Some("so") {
-                        if let Some(fname) = path.file_name().and_then(|s| s.to_str()) {
-                            return Some(fname.to_string());
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    // Fallback: derive from Cargo.toml (use lib{name}.so which is what Rust produces for cdylib)
-    let cargo_toml = fs::read_to_string("Cargo.toml").ok()?;
-    let value = cargo_toml.parse::<Value>().ok()?;
-    let pkg_name = value.get("package")?.get("name")?.as_str()?;
-    Some(format!("lib{}.so", pkg_name))
+fn get_lib_name(project: &Project) -> Option<String> {
+    project.package().cdylib_file_name()
 }
 
 // arm64-v8a: Will link to targets/aarch64-linux-android/release/{libname}
@@ -52,13 +17,10 @@ fn get_lib_name(mut mode: &str) -> Option<String> {
 // x86_64: Will link to targets/x86_64-linux-android/release/{libname}
 
 // Links will be put in android/app/src/main/jniLibs
-pub fn create_symbolic_links(mut mode: &str) {
-    let lib_name = match get_lib_name(mode) {
-        Some(n) => n,
-        None => {
-            eprintln!("Could not determine library name from Cargo.toml");
-            return;
-        }
+pub fn create_symbolic_links(mut mode: &str) -> Result<()> {
+    let project = Project::load().context("Failed to read cargo metadata")?;
+    let Some(lib_name) = get_lib_name(&project) else {
+        bail!("Package `{}` has no cdylib target", project.package().name);
     };
 
     let mappings = [
@@ -68,7 +30,6 @@ pub fn create_symbolic_links(mut mode: &str) {
         ("x86_64", "x86_64-linux-android"),
     ];
 
-    let project_root = env::current_dir().unwrap_or_else(|_| Path::new(".").to_path_buf());
     let jni_base = Path::new("android").join("app").join("src").join("main").join("jniLibs");
     if mode == "--release" {
         mode = "release";
@@ -77,40 +38,32 @@ pub fn create_symbolic_links(mut mode: &str) {
         mode = "debug";
     }
     for (abi_dir, target_triple) in &mappings {
-        let target_path = project_root
-            .join("target")
+        let target_path = project
+            .target_directory
             .join(target_triple)
             .join(mode)
             .join(&lib_name);
 
         let dest_dir = jni_base.join(abi_dir);
-        if let Err(e) = fs::create_dir_all(&dest_dir) {
-            eprintln!("Failed to create {:?}: {}", dest_dir, e);
-            continue;
-        }
+        fs::create_dir_all(&dest_dir)
+            .with_context(|| format!("Failed to create {}", dest_dir.display()))?;
 
         let dest_link = dest_dir.join(&lib_name);
 
-        if dest_link.exists() {
-            if let Err(e) = fs::remove_file(&dest_link) {
-                eprintln!("Failed to remove existing {:?}: {}", dest_link, e);
-                // continue attempting to create the new link anyway
-            }
+        // symlink_metadata so a dangling link from an earlier build is replaced too
+        if fs::symlink_metadata(&dest_link).is_ok() {
+            fs::remove_file(&dest_link)
+                .with_context(|| format!("Failed to remove existing {}", dest_link.display()))?;
         }
 
         #[cfg(target_os = "linux")]
-        {
-            if let Err(e) = std::os::unix::fs::symlink(&target_path, &dest_link) {
-                eprintln!("Failed to create symlink {:?} -> {:?}: {}", dest_link, target_path, e);
-            }
-        }
+        std::os::unix::fs::symlink(&target_path, &dest_link)
+            .with_context(|| format!("Failed to create symlink {} -> {}", dest_link.display(), target_path.display()))?;
 
+        // Use file symlink on Windows
         #[cfg(target_os = "windows")]
-        {
-            // Use file symlink on Windows
-            if let Err(e) = std::os::windows::fs::symlink_file(&target_path, &dest_link) {
-                eprintln!("Failed to create symlink {:?} -> {:?}: {}", dest_link, target_path, e);
-            }
-        }
+        std::os::windows::fs::symlink_file(&target_path, &dest_link)
+            .with_context(|| format!("Failed to create symlink {} -> {}", dest_link.display(), target_path.display()))?;
     }
-}
\ No newline at end of file
+    Ok(())
+}
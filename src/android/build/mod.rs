@@ -1,11 +1,27 @@
 mod compile;
 mod jni;
 mod bundle;
+mod assets;
 use compile::build_android_targets;
 use jni::create_symbolic_links;
 use bundle::begin_gradle_build;
-pub fn begin_build(mode: &str) {
-    build_android_targets(mode).expect("Failed to build android targets");
-    create_symbolic_links(mode);
-    begin_gradle_build();
-}
\ No newline at end of file
+use assets::copy_resources;
+use crate::error::Result;
+use crate::events::{self, Event};
+pub fn begin_build(mode: &str) -> Result<()> {
+    let profile = if mode == "--release" { "release" } else { "dev" };
+    events::emit(&Event::BuildStarted {
+        packages: Vec::new(),
+        targets: compile::TARGETS.to_vec(),
+        profile,
+    });
+    build_android_targets(mode)?;
+    create_symbolic_links(mode)?;
+    let result = copy_resources().and_then(|_| begin_gradle_build());
+    events::emit(&Event::BuildFinished {
+        success: result.is_ok(),
+        artifacts: *result.as_ref().unwrap_or(&0),
+        failures: usize::from(result.is_err()),
+    });
+    result.map(|_| ())
+}
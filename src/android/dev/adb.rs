@@ -1,9 +1,14 @@
 use std::process::Command;
 use std::env;
+use std::path::PathBuf;
+use anyhow::anyhow;
+use crate::error::{Error, Result};
 
-fn find_adb() -> String {
-    let android_sdk = env::var("ANDROID_HOME").expect("ANDROID_HOME not set");
-    format!("{}\\platform-tools\\adb.exe", android_sdk)
+fn find_adb() -> Result<PathBuf> {
+    let android_sdk = env::var_os("ANDROID_HOME")
+        .ok_or_else(|| Error::Config(anyhow!("ANDROID_HOME is not set")))?;
+    let adb = format!("adb{}", env::consts::EXE_SUFFIX);
+    Ok(PathBuf::from(android_sdk).join("platform-tools").join(adb))
 }
 
 // pub fn start_server() {
@@ -17,11 +22,14 @@ fn find_adb() -> String {
 //     ;
 // }
 
-pub fn perform_streamed_install() {
-    let adb = find_adb();
-    let _ = Command::new(adb)
-    .args(["install", "android/app/build/outputs/apk/debug/app-debug.apk"])
-    .status();
-
+pub fn perform_streamed_install() -> Result<()> {
+    let adb = find_adb()?;
+    let status = Command::new(&adb)
+        .args(["install", "android/app/build/outputs/apk/debug/app-debug.apk"])
+        .status()
+        .map_err(|_| Error::missing_tool("adb"))?;
+    if !status.success() {
+        return Err(Error::Other(anyhow!("adb install failed with {}", status)));
+    }
+    Ok(())
 }
-
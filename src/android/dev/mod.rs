@@ -1,52 +1,48 @@
 mod adb;
 
-use notify::{RecommendedWatcher, RecursiveMode, Watcher, EventKind};
+use notify::{RecommendedWatcher, Watcher, EventKind};
 use std::process::{Child};
+use anyhow::Context as _;
 use std::sync::mpsc::channel;
 use std::time::Duration;
 use crate::android::build::begin_build;
+use crate::dev::watch;
+use crate::error::Result;
+use crate::events;
 use adb::perform_streamed_install;
 
 
-pub fn handle_dev() {
+pub fn handle_dev() -> Result<()> {
     let (tx, rx) = channel();
-    let mut watcher = RecommendedWatcher::new(tx, notify::Config::default()).expect("Failed to create watcher");
-    let src_path = std::path::Path::new("src").canonicalize().expect("Failed to canonicalize src path");
-    let ui_path = std::path::Path::new("ui").canonicalize().expect("Failed to canonicalize ui path");
-    let cargo_path = std::path::Path::new("Cargo.toml").canonicalize().expect("Failed to canonicalize Cargo manifest Path");
-    println!("Watching: {}", src_path.display());
-    watcher.watch(&src_path, RecursiveMode::Recursive).expect("Failed to watch src");
-
-    println!("Watching: {}", ui_path.display());
-    watcher.watch(&ui_path, RecursiveMode::Recursive).expect("Failed to watch ui");
-
-    println!("Watching {}", cargo_path.display());
-    watcher.watch(&cargo_path, RecursiveMode::Recursive).expect("Failed to watch Cargo.toml");
+    let mut watcher = RecommendedWatcher::new(tx, notify::Config::default())
+        .context("failed to create file watcher")?;
+    watch(&mut watcher)?;
 
     // Compile and run at the start
     println!("Initial build (dev profile)...");
     let mut child: Option<Child> = None;
     println!("Building Application Initially");
-    begin_build("--dev");
-    perform_streamed_install();
+    if let Err(e) = begin_build("--dev") {
+        events::error(&e);
+    }
+    perform_streamed_install()?;
 
     loop {
         // println!("Waiting for file changes...");
-        match rx.recv_timeout(Duration::from_millis(500)) {
-            Ok(event) => {
-                if let Ok(event) = event {
-                    if matches!(event.kind, EventKind::Modify(_)) {
-                        if let Some(mut c) = child.take() {
-                            let _ = c.kill();
-                            let _ = c.wait();
-                        }
-                        println!("Rebuilding (dev profile)...");
-                        begin_build("");
-                        perform_streamed_install();
-                    }
+        if let Ok(Ok(event)) = rx.recv_timeout(Duration::from_millis(500)) {
+            if matches!(event.kind, EventKind::Modify(_)) {
+                if let Some(mut c) = child.take() {
+                    let _ = c.kill();
+                    let _ = c.wait();
+                }
+                println!("Rebuilding (dev profile)...");
+                if let Err(e) = begin_build("") {
+                    events::error(&e);
+                }
+                if let Err(e) = perform_streamed_install() {
+                    events::error(&e);
                 }
             }
-            Err(_) => {}
         }
     }
 }
@@ -1,26 +1,53 @@
-use std::fs;
+use std::{env, fs};
 use std::path::{Path, PathBuf};
 use crate::icon::generate_android_icons;
-use crate::utils::{read_package_metadata};
-
-
-
-fn find_path() -> String {
-    let username = whoami::username();
-    #[cfg(target_os = "windows")]
-    let paths = [
-        format!("C:/Users/{}/.cargo/bin", username),
-        "$ANDROID_NDK/toolchains/llvm/prebuilt/windows-x86_64/bin;".to_string(),
-        format!("C:/Users/{}/AppData/Local/Android/Sdk/platform-tools;", username),
-        "C:/Program Files (x86)/Microsoft Visual Studio/2022/BuildTools/Common7/IDE/CommonExtensions/Microsoft/CMake/Ninja;".to_string(),
-        "C:/Program Files/Android/Android Studio/jbr/bin".to_string()
-    ];
-
-    let mut path_var = String::new();
-    for path in paths {
-        path_var.push_str(&path);
+use anyhow::{anyhow, Context as _};
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::events;
+use crate::project::Project;
+
+
+
+/// `PATH` for the generated Cargo config: cargo, the NDK's LLVM toolchain and the SDK's
+/// platform-tools, joined with the host's separator.
+fn find_path() -> Result<String> {
+    let home = env::home_dir().ok_or_else(|| Error::Config(anyhow!("cannot tell the home directory")))?;
+    let sdk = env::var_os("ANDROID_HOME").map(PathBuf::from).unwrap_or_else(|| {
+        if cfg!(target_os = "windows") {
+            home.join("AppData/Local/Android/Sdk")
+        } else if cfg!(target_os = "macos") {
+            home.join("Library/Android/sdk")
+        } else {
+            home.join("Android/Sdk")
+        }
+    });
+    // ANDROID_NDK_HOME, else the newest NDK the SDK manager installed
+    let ndk = env::var_os("ANDROID_NDK_HOME").map(PathBuf::from).or_else(|| {
+        let versions = fs::read_dir(sdk.join("ndk")).ok()?;
+        versions.flatten().map(|entry| entry.path()).filter(|path| path.is_dir()).max()
+    });
+    let prebuilt = match env::consts::OS {
+        "windows" => "windows-x86_64",
+        "macos" => "darwin-x86_64",
+        _ => "linux-x86_64",
+    };
+
+    let mut paths = vec![home.join(".cargo/bin")];
+    match ndk {
+        Some(ndk) => paths.push(ndk.join("toolchains/llvm/prebuilt").join(prebuilt).join("bin")),
+        None => events::warning("no Android NDK found, set ANDROID_NDK_HOME"),
     }
-    path_var.to_string()
+    paths.push(sdk.join("platform-tools"));
+    if cfg!(target_os = "windows") {
+        paths.push(PathBuf::from(
+            "C:/Program Files (x86)/Microsoft Visual Studio/2022/BuildTools/Common7/IDE/CommonExtensions/Microsoft/CMake/Ninja",
+        ));
+        paths.push(PathBuf::from("C:/Program Files/Android/Android Studio/jbr/bin"));
+    }
+    let path = env::join_paths(paths).map_err(|e| Error::Config(anyhow!("invalid PATH entry: {}", e)))?;
+    // Forward slashes keep the TOML string free of escapes
+    Ok(path.to_string_lossy().replace('\\', "/"))
 }
 
 
@@ -30,13 +57,23 @@ struct AndroidTemplateFile {
     content: String,
 }
 
-pub fn initialize_android_project() -> std::io::Result<()> {
-    let (app_id, project_name, version, package_name) = read_package_metadata();
+pub fn initialize_android_project() -> Result<()> {
+    let project = Project::load().map_err(Error::Config)?;
+    let config = Config::for_package(&project, project.package()).map_err(Error::Config)?;
+    let lib_name = project
+        .package()
+        .cdylib()
+        .map(|t| t.name.replace('-', "_"))
+        .ok_or_else(|| Error::Config(anyhow!("the package has no cdylib target")))?;
     
     let target_dir = Path::new("android");
-    fs::create_dir_all(&target_dir)?;
+    fs::create_dir_all(target_dir)?;
 
-    let template_files = generate_template_files(&package_name, &version, &app_id, &project_name);
+    let version_code = match config.android.version_code {
+        Some(code) => code,
+        None => config.version.android_code().map_err(Error::Config)?,
+    };
+    let template_files = generate_template_files(&config, &lib_name, version_code, &find_path()?);
     
     for template in template_files {
         let full_path = target_dir.join(&template.path);
@@ -45,20 +82,23 @@ pub fn initialize_android_project() -> std::io::Result<()> {
         }
         fs::write(full_path, template.content)?;
     }
-    generate_android_icons().expect("Failed to generate icons");
+    generate_android_icons().map_err(|e| anyhow!("failed to generate Android icons: {}", e))?;
     
     Ok(())
 }
 
-pub fn create_jni_dirs() {
+pub fn create_jni_dirs() -> Result<()> {
     for abi in ANDROID_ABIS.iter() {
         let path = format!("android/app/src/main/jniLibs/{}", abi);
-        fs::create_dir_all(&path).expect("Failed to create directory");
+        fs::create_dir_all(&path).with_context(|| format!("failed to create {}", path))?;
         println!("Created directory: {}", path);
     }
+    Ok(())
 }
 
-fn generate_template_files(package_name: &str, _version: &str, app_id: &str, project_name: &str) -> Vec<AndroidTemplateFile> {
+fn generate_template_files(config: &Config, lib_name: &str, version_code: u32, path: &str) -> Vec<AndroidTemplateFile> {
+    let package_name = &config.name;
+    let app_id = &config.identifier;
     let mut files = Vec::new();
     files.push(AndroidTemplateFile {
         path: PathBuf::from("gradlew"),
@@ -459,7 +499,7 @@ ANDROID_NDK = "C:/Users/{username}/AppData/Local/Android/Sdk/ndk/27.0.12077973"
 JAVA_HOME = "C:/Program Files/Android/Android Studio/jbr"
 PATH="{path}"
 ANDROID_PLATFORM = "android-33"
-        "#, username = whoami::username(), path = find_path())
+        "#, username = whoami::username(), path = path)
     });
     // Root build.gradle.kts
     files.push(AndroidTemplateFile {
@@ -502,14 +542,14 @@ plugins {{
 apply plugin: 'com.android.application'
 android {{
     namespace "{app_id}"
-    compileSdkVersion 33
+    compileSdkVersion {target_sdk}
 
     defaultConfig {{
         applicationId "{app_id}"
-        minSdkVersion 21
-        targetSdkVersion 33
-        versionCode 1
-        versionName "1.0"
+        minSdkVersion {min_sdk}
+        targetSdkVersion {target_sdk}
+        versionCode {version_code}
+        versionName "{version}"
     }}
 
     sourceSets.main {{
@@ -519,7 +559,12 @@ android {{
 
     // No Kotlin/Java sources are required for this NativeActivity example.
 }}
-"#, app_id = app_id)
+"#,
+            app_id = app_id,
+            min_sdk = config.android.min_sdk,
+            target_sdk = config.android.target_sdk,
+            version_code = version_code,
+            version = config.version)
     });
     files.push(AndroidTemplateFile {
         path: PathBuf::from("app/src/main/AndroidManifest.xml"),
@@ -538,7 +583,7 @@ android {{
             android:configChanges="orientation|screenSize">
 
             <!-- Name of the native library (libmain.so -> "main") -->
-            <meta-data android:name="android.app.lib_name" android:value="{package_name}" />
+            <meta-data android:name="android.app.lib_name" android:value="{lib_name}" />
 
             <intent-filter>
                 <action android:name="android.intent.action.MAIN" />
@@ -546,13 +591,13 @@ android {{
             </intent-filter>
         </activity>
     </application>
-</manifest>"#,app_id = app_id, package_name = package_name.replace("-", "_"))
+</manifest>"#,app_id = app_id, lib_name = lib_name)
     });
     files.push(AndroidTemplateFile {
         path: PathBuf::from("app/src/main/res/values/strings.xml"),
         content: format!(r#"<resources>
     <string name="app_name">{app_name}</string>
-</resources>"#, app_name = project_name)
+</resources>"#, app_name = config.display_name)
     });
     files.push(AndroidTemplateFile {
         path: PathBuf::from("gradle/wrapper/gradle-wrapper.properties"),
@@ -6,25 +6,26 @@ mod sign;
 use init::{initialize_android_project, create_jni_dirs};
 use tools::unpack_gradle_jar;
 use sign::handle_sign;
-use crate::help::generate_help_message;
+use crate::cli::AndroidCommand;
+use crate::error::Result;
 
 pub use build::begin_build;
 use dev::handle_dev;
 // pub const GRADLE_VERSION: &str = "8.9";
 // pub const BUNDLE_TOOL_VERSION: &str = "1.18.2";
-pub fn handle_android(args: &[String]) {
-    match args[2].as_str() {
-        "init" => {
-            initialize_android_project().expect("Failed to initialize android project");
-            create_jni_dirs();
-            unpack_gradle_jar();
+pub fn handle_android(command: AndroidCommand) -> Result<()> {
+    match command {
+        AndroidCommand::Init => {
+            initialize_android_project()?;
+            create_jni_dirs()?;
+            unpack_gradle_jar()?;
 
         },
-        "build" => begin_build("--release"),
-        "dev" => handle_dev(),
-        "key" => handle_sign(args),
-        _ => println!("{}", generate_help_message(&args))
+        AndroidCommand::Build { .. } => begin_build("--release")?,
+        AndroidCommand::Dev => handle_dev()?,
+        AndroidCommand::Key { command } => handle_sign(command)?,
     }
+    Ok(())
     //unpack_gradle_jar(GRADLE_WRAPPER_PATH);
     //begin_build();
 }
@@ -1,7 +1,6 @@
-
 use std::io::{self, Write};
 
-pub fn get_distinguished_names() -> String {
+pub fn get_distinguished_names() -> io::Result<String> {
     let questions = [
         "What is your first and last name?", 
         "What is the name of your organizational unit?", 
@@ -16,18 +15,18 @@ pub fn get_distinguished_names() -> String {
     
     for question in questions {
         let mut answer = String::new();
-        print!("{}", question);
-        io::stdout().flush().unwrap();
-        io::stdin().read_line(&mut answer).expect("Failed to read line");
+        print!("{} ", question);
+        io::stdout().flush()?;
+        io::stdin().read_line(&mut answer)?;
         answers.push(answer.trim().to_string());
     }
-    format!(
-        "CN={cn}, OU={ou}, O={o}, L={l}, ST={st} C={c}", 
-        cn = questions[0],
-        ou = questions[1],
-        o = questions[2],
-        l = questions[3],
-        st = questions[4],
-        c = questions[5]
-    )
-}
\ No newline at end of file
+    Ok(format!(
+        "CN={cn}, OU={ou}, O={o}, L={l}, ST={st}, C={c}", 
+        cn = answers[0],
+        ou = answers[1],
+        o = answers[2],
+        l = answers[3],
+        st = answers[4],
+        c = answers[5]
+    ))
+}
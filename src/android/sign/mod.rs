@@ -1,35 +1,37 @@
 #[cfg(target_os="windows")]
 mod windows;
 mod key;
-use std::env;
+use std::path::Path;
 use std::process::Command;
+use anyhow::anyhow;
 
 use std::io::{self, Write};
 use key::get_distinguished_names;
-use crate::utils::{read_package_metadata};
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::project::Project;
 use crate::utils::find_build_tools;
-use crate::help::generate_help_message;
+use crate::cli::KeyCommand;
 // #[cfg(target_os="windows")]
 // use windows::KEYTOOL;
 
-pub fn handle_sign(args: &[String]) {
-    match args[3].as_str() {
-        "generate" => generate_key(&args[4]),
-        "sign" => sign_bundle(args[4].clone()),
-        _ => println!("{}", generate_help_message(&args))
+pub fn handle_sign(command: KeyCommand) -> Result<()> {
+    match command {
+        KeyCommand::Generate { keyfile } => generate_key(&keyfile),
+        KeyCommand::Sign { keystore } => sign_bundle(keystore),
     }
 }
-fn generate_key(keyfile: &str) {
+fn generate_key(keyfile: &str) -> Result<()> {
 
     let mut alias = String::new();
     print!("Please enter your keystore Alias: ");
-    io::stdout().flush().unwrap();
-    io::stdin().read_line(&mut alias).expect("Failed to read line");
+    io::stdout().flush()?;
+    io::stdin().read_line(&mut alias)?;
     alias = alias.trim().to_string();
 
-    let dname = &get_distinguished_names();
+    let dname = &get_distinguished_names()?;
 
-    let _ = Command::new("keytool")
+    let status = Command::new("keytool")
                 .args(
                     [
                         "-genkey", 
@@ -38,26 +40,45 @@ fn generate_key(keyfile: &str) {
                         "-keyalg", 
                         "RSA", 
                         "-keystore", 
-                        &keyfile, 
+                        keyfile, 
                         "-keysize", 
                         "2048",
                         "-dname",
                         dname
                     ]
                 )
-                .status();
+                .status()
+                .map_err(|_| Error::missing_tool("keytool"))?;
+    if !status.success() {
+        return Err(Error::Other(anyhow!("keytool failed with {}", status)));
+    }
+    Ok(())
 }
 
 
-fn sign_bundle(keystore: String) {
-    let (_app_id, _project_name, _version, package_name) = read_package_metadata();
-    let apk_signer = format!("{}\\apksigner.bat", find_build_tools());
-    println!("{}", apk_signer);
-    let current_path: String = env::var("PATH").unwrap_or_default();
-    env::set_var("PATH", format!("{};{}", current_path, apk_signer));
-    let apk_path = "android\\app\\build\\outputs\\apk\\release\\app-release-unsigned.apk";
+fn sign_bundle(keystore: String) -> Result<()> {
+    let project = Project::load().map_err(Error::Config)?;
+    let package_name = Config::for_package(&project, project.package())
+        .map_err(Error::Config)?
+        .name;
+    let apk_signer = find_build_tools()?.join(if cfg!(windows) { "apksigner.bat" } else { "apksigner" });
+    println!("{}", apk_signer.display());
+    let apk_path = Path::new("android/app/build/outputs/apk/release/app-release-unsigned.apk");
+    if !apk_path.exists() {
+        return Err(Error::Config(anyhow!(
+            "{} not found, run `slint-bundler android build` first",
+            apk_path.display()
+        )));
+    }
     let output_path = format!("{}-signed.apk", package_name);
-    let _ = Command::new(apk_signer)
-                .args(["sign", "--ks", keystore.as_str(), "--in", apk_path, "--out", &output_path])
-                .status();
-}
\ No newline at end of file
+    let status = Command::new(&apk_signer)
+                .args(["sign", "--ks", keystore.as_str(), "--in"])
+                .arg(apk_path)
+                .args(["--out", &output_path])
+                .status()
+                .map_err(|_| Error::missing_tool("apksigner"))?;
+    if !status.success() {
+        return Err(Error::Other(anyhow!("apksigner failed with {}", status)));
+    }
+    Ok(())
+}
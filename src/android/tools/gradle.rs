@@ -1,5 +1,5 @@
 
 const GRADLE_JAR: &[u8; 45457] = include_bytes!("./gradle-wrapper.jar");
-pub fn unpack_gradle_jar() {
-    std::fs::write("android/gradle/wrapper/gradle-wrapper.jar", GRADLE_JAR).expect("Failed to write gradle wrapper");
+pub fn unpack_gradle_jar() -> std::io::Result<()> {
+    std::fs::write("android/gradle/wrapper/gradle-wrapper.jar", GRADLE_JAR)
 }
\ No newline at end of file
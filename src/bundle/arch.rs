@@ -0,0 +1,136 @@
+use anyhow::{bail, Result};
+
+/// CPU architecture of the binary being bundled, derived from its target triple.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Arch {
+    X86_64,
+    Aarch64,
+    Armhf,
+    /// Soft-float 32-bit ARM, e.g. `arm-unknown-linux-gnueabi`.
+    Armel,
+    I686,
+    Riscv64,
+}
+
+impl Arch {
+    pub const ALL: [Arch; 6] = [Arch::X86_64, Arch::Aarch64, Arch::Armhf, Arch::Armel, Arch::I686, Arch::Riscv64];
+
+    /// `aarch64-unknown-linux-gnu` -> `Aarch64`, `armv7-unknown-linux-gnueabihf` -> `Armhf`.
+    /// Fails for CPUs the package formats have no architecture name for.
+    pub fn from_triple(triple: &str) -> Result<Arch> {
+        let cpu = triple.split('-').next().unwrap_or(triple);
+        Ok(match cpu {
+            "x86_64" => Arch::X86_64,
+            "aarch64" | "arm64" => Arch::Aarch64,
+            "i386" | "i586" | "i686" | "x86" => Arch::I686,
+            "riscv64" | "riscv64gc" => Arch::Riscv64,
+            c if (c.starts_with("arm") || c.starts_with("thumb")) && !c.contains("eb") => {
+                // Only the hard-float ABIs are armhf; `gnueabi` and `musleabi` are soft-float
+                if triple.ends_with("eabi") { Arch::Armel } else { Arch::Armhf }
+            }
+            _ => bail!("unsupported CPU architecture `{}` in target `{}`", cpu, triple),
+        })
+    }
+
+    /// Architecture of the machine running the bundler, for when rustc cannot tell the
+    /// host triple.
+    pub fn host() -> Result<Arch> {
+        Arch::from_triple(std::env::consts::ARCH)
+    }
+
+    /// Format-independent name used in messages, e.g. `x86_64`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Arch::X86_64 => "x86_64",
+            Arch::Aarch64 => "aarch64",
+            Arch::Armhf => "armhf",
+            Arch::Armel => "armel",
+            Arch::I686 => "i686",
+            Arch::Riscv64 => "riscv64",
+        }
+    }
+
+    /// Debian `Architecture:` value.
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    pub fn deb(self) -> &'static str {
+        match self {
+            Arch::X86_64 => "amd64",
+            Arch::Aarch64 => "arm64",
+            Arch::Armhf => "armhf",
+            Arch::Armel => "armel",
+            Arch::I686 => "i386",
+            Arch::Riscv64 => "riscv64",
+        }
+    }
+
+    /// RPM `%{_arch}` value.
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    pub fn rpm(self) -> &'static str {
+        match self {
+            Arch::X86_64 => "x86_64",
+            Arch::Aarch64 => "aarch64",
+            Arch::Armhf => "armv7hl",
+            Arch::Armel => "armv5tel",
+            Arch::I686 => "i686",
+            Arch::Riscv64 => "riscv64",
+        }
+    }
+
+    /// Arch Linux `arch` value used in pacman package names.
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    pub fn pacman(self) -> &'static str {
+        match self {
+            Arch::X86_64 => "x86_64",
+            Arch::Aarch64 => "aarch64",
+            Arch::Armhf => "armv7h",
+            Arch::Armel => "arm",
+            Arch::I686 => "i686",
+            Arch::Riscv64 => "riscv64",
+        }
+    }
+
+    /// Architecture suffix appimagetool uses for AppImage file names.
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    pub fn appimage(self) -> &'static str {
+        match self {
+            Arch::X86_64 => "x86_64",
+            Arch::Aarch64 => "aarch64",
+            Arch::Armhf => "armhf",
+            Arch::Armel => "armel",
+            Arch::I686 => "i686",
+            Arch::Riscv64 => "riscv64",
+        }
+    }
+
+    /// MSIX `ProcessorArchitecture` value.
+    pub fn msix(self) -> &'static str {
+        match self {
+            Arch::X86_64 => "x64",
+            Arch::Aarch64 => "arm64",
+            Arch::Armhf | Arch::Armel => "arm",
+            Arch::I686 => "x86",
+            Arch::Riscv64 => "neutral",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_triple() {
+        assert_eq!(Arch::from_triple("x86_64-unknown-linux-gnu").unwrap(), Arch::X86_64);
+        assert_eq!(Arch::from_triple("aarch64-unknown-linux-musl").unwrap(), Arch::Aarch64);
+        assert_eq!(Arch::from_triple("i686-pc-windows-msvc").unwrap(), Arch::I686);
+        assert_eq!(Arch::from_triple("riscv64gc-unknown-linux-gnu").unwrap(), Arch::Riscv64);
+        assert_eq!(Arch::from_triple("armv7-unknown-linux-gnueabihf").unwrap(), Arch::Armhf);
+        assert_eq!(Arch::from_triple("arm-unknown-linux-gnueabihf").unwrap(), Arch::Armhf);
+        assert_eq!(Arch::from_triple("thumbv7neon-unknown-linux-gnueabihf").unwrap(), Arch::Armhf);
+        assert_eq!(Arch::from_triple("arm-unknown-linux-gnueabi").unwrap(), Arch::Armel);
+        assert_eq!(Arch::from_triple("armv5te-unknown-linux-musleabi").unwrap(), Arch::Armel);
+        for triple in ["powerpc64le-unknown-linux-gnu", "s390x-unknown-linux-gnu", "loongarch64-unknown-linux-gnu", "armebv7r-none-eabihf"] {
+            assert!(Arch::from_triple(triple).is_err(), "{}", triple);
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use std::path::PathBuf;
+use anyhow::Result;
+use crate::bundle::plan::Plan;
+use crate::bundle::Context;
+#[cfg(target_os = "linux")]
+use crate::bundle::linux;
+#[cfg(target_os = "windows")]
+use crate::bundle::windows;
+
+/// A file written by a bundler.
+#[derive(Clone, Debug)]
+pub struct Artifact {
+    pub path: PathBuf,
+}
+
+/// A package format `build` can produce.
+pub trait Bundler: Sync {
+    /// Name used with `--bundles`, e.g. `deb`.
+    fn name(&self) -> &'static str;
+
+    /// `std::env::consts::OS` values this format can be created on.
+    fn supported_hosts(&self) -> &'static [&'static str];
+
+    /// Describe the staged files, tools and artifacts without compiling or writing anything.
+    fn plan(&self, ctx: &Context) -> Result<Plan>;
+
+    fn bundle(&self, ctx: &Context) -> Result<Vec<Artifact>>;
+}
+
+static BUNDLERS: &[&dyn Bundler] = &[
+    #[cfg(target_os = "linux")]
+    &linux::Deb,
+    #[cfg(target_os = "linux")]
+    &linux::Rpm,
+    #[cfg(target_os = "linux")]
+    &linux::TarZst,
+    #[cfg(target_os = "linux")]
+    &linux::TarXz,
+    #[cfg(target_os = "linux")]
+    &linux::AppImage,
+    #[cfg(target_os = "windows")]
+    &windows::Msi,
+    #[cfg(target_os = "windows")]
+    &windows::Nsis,
+    #[cfg(target_os = "windows")]
+    &windows::Msix,
+];
+
+/// Every bundler compiled into this binary, in the order they run by default.
+pub fn registry() -> &'static [&'static dyn Bundler] {
+    BUNDLERS
+}
+
+/// Look up a bundler by its `--bundles` name.
+pub fn find(name: &str) -> Option<&'static dyn Bundler> {
+    registry().iter().copied().find(|b| b.name() == name)
+}
+
+/// Bundlers that run on the current OS when `--bundles` is not given.
+pub fn defaults() -> Vec<&'static str> {
+    registry()
+        .iter()
+        .filter(|b| b.supported_hosts().contains(&std::env::consts::OS))
+        .map(|b| b.name())
+        .collect()
+}
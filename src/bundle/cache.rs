@@ -0,0 +1,189 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Context as _, Result};
+use serde::{Deserialize, Serialize};
+use crate::bundle::bundler::Artifact;
+use crate::bundle::Context;
+
+/// Artifacts of earlier bundles under `target/slint-bundler/cache`, reused while their inputs
+/// hash the same.
+pub struct Cache {
+    dir: PathBuf,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    fingerprint: String,
+    artifacts: Vec<CachedArtifact>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedArtifact {
+    path: PathBuf,
+    sha256: String,
+}
+
+impl Cache {
+    pub fn new(target_directory: &Path) -> Cache {
+        Cache {
+            dir: target_directory.join("slint-bundler").join("cache"),
+        }
+    }
+
+    fn entry_path(&self, package: &str, target: &str, format: &str) -> PathBuf {
+        self.dir.join(package).join(target).join(format!("{}.json", format))
+    }
+
+    /// Artifacts recorded for `fingerprint`, if all of them are still on disk unchanged.
+    pub fn lookup(&self, package: &str, target: &str, format: &str, fingerprint: &str) -> Option<Vec<Artifact>> {
+        let json = fs::read_to_string(self.entry_path(package, target, format)).ok()?;
+        let entry: Entry = serde_json::from_str(&json).ok()?;
+        if entry.fingerprint != fingerprint {
+            return None;
+        }
+        entry
+            .artifacts
+            .into_iter()
+            .map(|a| {
+                let sha256 = sha256::try_digest(a.path.as_path()).ok()?;
+                (sha256 == a.sha256).then_some(Artifact { path: a.path })
+            })
+            .collect()
+    }
+
+    pub fn store(&self, package: &str, target: &str, format: &str, fingerprint: &str, artifacts: &[Artifact]) -> Result<()> {
+        let entry = Entry {
+            fingerprint: fingerprint.to_string(),
+            artifacts: artifacts
+                .iter()
+                .map(|a| {
+                    Ok(CachedArtifact {
+                        path: a.path.clone(),
+                        sha256: sha256::try_digest(a.path.as_path())
+                            .with_context(|| format!("failed to hash {}", a.path.display()))?,
+                    })
+                })
+                .collect::<Result<_>>()?,
+        };
+        let path = self.entry_path(package, target, format);
+        fs::create_dir_all(path.parent().unwrap()).context("failed to create cache dir")?;
+        fs::write(&path, serde_json::to_string(&entry)?).with_context(|| format!("failed to write {}", path.display()))
+    }
+}
+
+/// Hash of everything that goes into one bundle: this tool's version, the format, the
+/// configuration, the binaries, the icons, the resources and, when fixed, the timestamp.
+pub fn fingerprint(ctx: &Context, format: &str, binary_sha256: &str, source_date: Option<u64>) -> String {
+    let mut inputs = String::new();
+    let _ = writeln!(inputs, "slint-bundler {}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(inputs, "format {}", format);
+    let _ = writeln!(inputs, "arch {:?}", ctx.arch);
+    let _ = writeln!(inputs, "out {}", ctx.bundle_dir.display());
+    let _ = writeln!(inputs, "target {}", ctx.target);
+    // Only names that contain it change with every commit
+    if ctx.config.artifact_name.as_deref().is_some_and(|t| t.contains("{git_sha}")) {
+        let _ = writeln!(inputs, "git {}", ctx.git_sha);
+    }
+    let _ = writeln!(inputs, "mtime {:?}", source_date);
+    let _ = writeln!(inputs, "config {:?}", ctx.config);
+    let _ = writeln!(inputs, "binary {}", binary_sha256);
+    for binary in ctx.binaries.iter().filter(|b| b.path != ctx.binary) {
+        let hash = sha256::try_digest(binary.path.as_path()).unwrap_or_default();
+        let _ = writeln!(inputs, "binary {} {}", binary.path.display(), hash);
+    }
+    // The same icons the bundlers read
+    let mut icons: Vec<PathBuf> = fs::read_dir(&ctx.icons_dir)
+        .map(|dir| dir.flatten().map(|e| e.path()).filter(|p| p.is_file()).collect())
+        .unwrap_or_default();
+    icons.sort();
+    for icon in icons {
+        let hash = sha256::try_digest(icon.as_path()).unwrap_or_default();
+        let _ = writeln!(inputs, "icon {} {}", icon.display(), hash);
+    }
+    for resource in &ctx.config.resources {
+        let hash = sha256::try_digest(resource.source.as_path()).unwrap_or_default();
+        let _ = writeln!(inputs, "resource {} {}", resource.source.display(), hash);
+    }
+    for (name, file) in [("license", &ctx.config.license_file), ("changelog", &ctx.config.linux.changelog)] {
+        if let Some(file) = file {
+            let hash = sha256::try_digest(file.as_path()).unwrap_or_default();
+            let _ = writeln!(inputs, "{} {} {}", name, file.display(), hash);
+        }
+    }
+    for (name, script) in ctx.config.linux.scripts.iter() {
+        let hash = sha256::try_digest(script.as_path()).unwrap_or_default();
+        let _ = writeln!(inputs, "{} {} {}", name, script.display(), hash);
+    }
+    sha256::digest(inputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bundle::tests::context;
+    use crate::config::{Config, WorkspaceDefaults};
+
+    fn config(dir: &Path) -> Config {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(dir.join("Cargo.toml"), "[package]\nname = \"app\"\nversion = \"1.0.0\"\n").unwrap();
+        Config::load_from(dir, &WorkspaceDefaults::default()).unwrap()
+    }
+
+    fn hash(path: &Path) -> String {
+        sha256::try_digest(path).unwrap()
+    }
+
+    #[test]
+    fn fingerprint_follows_the_inputs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = config(tmp.path());
+        let ctx = context(&config, tmp.path());
+        fs::write(&ctx.binary, b"binary").unwrap();
+        let base = fingerprint(&ctx, "deb", &hash(&ctx.binary), None);
+        assert_eq!(fingerprint(&ctx, "deb", &hash(&ctx.binary), None), base);
+        assert_ne!(fingerprint(&ctx, "rpm", &hash(&ctx.binary), None), base);
+
+        fs::write(&ctx.binary, b"rebuilt").unwrap();
+        let rebuilt = fingerprint(&ctx, "deb", &hash(&ctx.binary), None);
+        assert_ne!(rebuilt, base);
+
+        let mut changed = config.clone();
+        changed.description = "Changed".to_string();
+        let reconfigured = fingerprint(&context(&changed, tmp.path()), "deb", &hash(&ctx.binary), None);
+        assert_ne!(reconfigured, rebuilt);
+
+        fs::create_dir(&ctx.icons_dir).unwrap();
+        fs::write(ctx.icons_dir.join("icon.png"), b"icon").unwrap();
+        let with_icon = fingerprint(&ctx, "deb", &hash(&ctx.binary), None);
+        assert_ne!(with_icon, rebuilt);
+        fs::write(ctx.icons_dir.join("icon.png"), b"new icon").unwrap();
+        assert_ne!(fingerprint(&ctx, "deb", &hash(&ctx.binary), None), with_icon);
+
+        // SOURCE_DATE_EPOCH
+        let dated = fingerprint(&ctx, "deb", &hash(&ctx.binary), Some(1_700_000_000));
+        assert_ne!(dated, fingerprint(&ctx, "deb", &hash(&ctx.binary), None));
+        assert_ne!(dated, fingerprint(&ctx, "deb", &hash(&ctx.binary), Some(1_700_000_001)));
+    }
+
+    #[test]
+    fn lookup_checks_the_artifacts() {
+        let tmp = tempfile::tempdir().unwrap();
+        let cache = Cache::new(tmp.path());
+        let path = tmp.path().join("app_1.0.0_amd64.deb");
+        fs::write(&path, b"package").unwrap();
+        let artifacts = [Artifact { path: path.clone() }];
+        cache.store("app", "host", "deb", "f1", &artifacts).unwrap();
+
+        let found = cache.lookup("app", "host", "deb", "f1").unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].path, path);
+        assert!(cache.lookup("app", "host", "deb", "f2").is_none());
+        assert!(cache.lookup("app", "host", "rpm", "f1").is_none());
+
+        fs::write(&path, b"modified").unwrap();
+        assert!(cache.lookup("app", "host", "deb", "f1").is_none());
+        fs::remove_file(&path).unwrap();
+        assert!(cache.lookup("app", "host", "deb", "f1").is_none());
+    }
+}
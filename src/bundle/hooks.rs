@@ -0,0 +1,39 @@
+use std::path::Path;
+use std::process::Command;
+use crate::error::{Error, Result};
+use crate::events::{self, status};
+
+/// Run the `hook` command through the shell in `dir`, with `env` added to its environment.
+/// A hook that cannot be started or exits unsuccessfully aborts the build.
+pub fn run(hook: &str, command: &str, dir: &Path, env: &[(&'static str, String)]) -> Result<()> {
+    status!("Running {} hook `{}`", hook, command);
+    let failed = |reason: String| Error::Hook {
+        hook: hook.to_string(),
+        command: command.to_string(),
+        reason,
+    };
+    let mut shell = shell();
+    shell.arg(command).current_dir(dir).stdout(events::child_stdout());
+    for (key, value) in env {
+        shell.env(key, value);
+    }
+    let status = shell.status().map_err(|e| failed(format!("failed to start: {}", e)))?;
+    if !status.success() {
+        return Err(failed(status.to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn shell() -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.arg("/C");
+    cmd
+}
+
+#[cfg(not(target_os = "windows"))]
+fn shell() -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c");
+    cmd
+}
@@ -1,120 +1,127 @@
 use std::process::Command;
-use std::path::Path;
 use std::fs;
-use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use anyhow::{Context as _, Result};
 use tempfile::tempdir;
-use image;
 
-use crate::bundle::linux::write_desktop_file;
+use crate::bundle::bundler::{Artifact, Bundler};
+use crate::bundle::linux::{append_tree, bin_name, desktop_file, gz_encoder, planned_files, stage};
+use crate::bundle::plan::Plan;
+use crate::bundle::staging::{EntryKind, Source, StagedTree};
+use crate::bundle::Context;
+use crate::events::{self, status};
 
 
-pub fn bundle_standalone() {
-    println!("Creating standalone AppImage...");
+pub struct AppImage;
 
-    // Read package name and version from Cargo.toml
-    let manifest = std::fs::read_to_string("Cargo.toml").expect("Failed to read Cargo.toml");
-    let package_name = manifest
-        .lines()
-        .find_map(|line| {
-            if line.trim_start().starts_with("name") {
-                line.split('=').nth(1).map(|v| v.trim().trim_matches('"').to_string())
-            } else {
-                None
-            }
-        })
-        .expect("Could not find package name in Cargo.toml");
-    let version = manifest
-        .lines()
-        .find_map(|line| {
-            if line.trim_start().starts_with("version") {
-                line.split('=').nth(1).map(|v| v.trim().trim_matches('"').to_string())
-            } else {
-                None
-            }
-        })
-        .unwrap_or_else(|| "0.1.0".to_string());
+impl Bundler for AppImage {
+    fn name(&self) -> &'static str {
+        "standalone"
+    }
 
-    // Ensure release binary exists
-    let release_bin = Path::new("target").join("release").join(&package_name);
-    if !release_bin.exists() {
-        eprintln!("Release binary not found at {}. Make sure `cargo build --release` ran successfully.", release_bin.display());
-        return;
+    fn supported_hosts(&self) -> &'static [&'static str] {
+        &["linux"]
     }
 
-    // Build an AppDir tree in a tempdir
-    let tmp = tempdir().expect("failed to create tempdir");
-    let appdir = tmp.path().join(format!("{}.AppDir", package_name));
-    let usr_bin = appdir.join("usr").join("bin");
-    let applications_dir = appdir.join("usr").join("share").join("applications");
-    let icons_root = appdir.join("usr").join("share").join("icons").join("hicolor");
-    fs::create_dir_all(&usr_bin).expect("failed to create usr/bin");
-    fs::create_dir_all(&applications_dir).expect("failed to create applications dir");
-    fs::create_dir_all(&icons_root).expect("failed to create icons root");
-
-    // Copy binary into AppDir usr/bin
-    let dest_bin = usr_bin.join(&package_name);
-    fs::copy(&release_bin, &dest_bin).expect("failed to copy binary into AppDir");
-    let mut perms = fs::metadata(&dest_bin).expect("meta").permissions();
-    perms.set_mode(0o755);
-    fs::set_permissions(&dest_bin, perms).expect("failed to set permissions");
-
-    // Write desktop file and icons into AppDir
-    write_desktop_file(&package_name, &applications_dir).expect("failed to write desktop file");
-    let icons_dir = Path::new("icons");
-    if icons_dir.exists() && icons_dir.is_dir() {
-        for entry in fs::read_dir(icons_dir).expect("failed to read icons dir").flatten() {
-            let path = entry.path();
-            if !path.is_file() { continue; }
-            if let Ok(img) = image::open(&path) {
-                let w = img.width();
-                let h = img.height();
-                let size_dir = icons_root.join(format!("{}x{}", w, h)).join("apps");
-                fs::create_dir_all(&size_dir).expect("failed to create icon size dir");
-                let dest = size_dir.join(format!("{}.png", package_name));
-                let mut fout = fs::File::create(&dest).expect("failed to create icon dest");
-                img.write_to(&mut fout, image::ImageFormat::Png).expect("failed to write icon png");
-            }
-        }
+    fn plan(&self, ctx: &Context) -> Result<Plan> {
+        // Without appimagetool only the AppDir archive fallback is written
+        let out_path = out_path(ctx);
+        let artifact = if which::which("appimagetool").is_ok() {
+            out_path
+        } else {
+            out_path.with_extension("tar.gz")
+        };
+        Ok(Plan {
+            files: planned_files(&appdir_tree(ctx)?),
+            tools: vec!["appimagetool"],
+            artifacts: vec![artifact],
+        })
     }
 
-    // derive arch
-    let arch = match std::env::consts::ARCH {
-        "x86_64" => "x86_64",
-        "aarch64" => "aarch64",
-        "arm" | "armv7" | "armv7l" => "armhfp",
-        "x86" | "i386" => "i386",
-        _ => "noarch",
-    };
-
-    // Prepare output path
-    let out_dir = Path::new("target").join("release").join("bundle").join("standalone");
-    fs::create_dir_all(&out_dir).expect("failed to create output bundle dir");
-    let out_path = out_dir.join(format!("{}_{}_{}.AppImage", package_name, version, arch));
-
-    // Prefer to use `appimagetool` if available; create AppImage from AppDir
-    if which::which("appimagetool").is_ok() {
-        // run appimagetool <AppDir> <outpath>
-        let status = Command::new("appimagetool")
-            .arg(&appdir)
-            .arg(&out_path)
-            .status()
-            .expect("failed to run appimagetool");
-        if status.success() {
-            println!("Created {}", out_path.display());
-            return;
+    fn bundle(&self, ctx: &Context) -> Result<Vec<Artifact>> {
+        status!("Creating standalone AppImage...");
+
+        let config = ctx.config;
+        let tree = appdir_tree(ctx)?;
+
+        let tmp = tempdir().context("failed to create tempdir")?;
+        let appdir = tmp.path().join(format!("{}.AppDir", config.name));
+        tree.write_to(&appdir).context("failed to write AppDir")?;
+
+        let arch = ctx.arch.appimage();
+
+        // Prepare output path
+        let out_path = out_path(ctx);
+        fs::create_dir_all(ctx.bundle_dir.join("standalone")).context("failed to create output bundle dir")?;
+
+        // Prefer to use `appimagetool` if available; create AppImage from AppDir
+        if which::which("appimagetool").is_ok() {
+            // run appimagetool <AppDir> <outpath>
+            let status = Command::new("appimagetool")
+                .env("ARCH", arch)
+                // mksquashfs clamps timestamps to it
+                .env("SOURCE_DATE_EPOCH", tree.mtime.to_string())
+                .arg(&appdir)
+                .arg(&out_path)
+                .stdout(events::child_stdout())
+                .status()
+                .context("failed to run appimagetool")?;
+            if status.success() {
+                status!("Created {}", out_path.display());
+                return Ok(vec![Artifact { path: out_path }]);
+            } else {
+                events::warning(format_args!("appimagetool failed ({}), falling back to an AppDir archive", status));
+            }
         } else {
-            eprintln!("appimagetool failed (exit {}). Falling back to creating AppDir archive.", status);
+            events::warning("appimagetool not found in PATH, creating an AppDir tar.gz instead (not a runnable AppImage)");
         }
-    } else {
-        eprintln!("appimagetool not found in PATH. Creating AppDir tar.gz as fallback (not a runnable AppImage).");
+
+        // Fallback: create a tar.gz snapshot of the AppDir (note: not a proper AppImage)
+        let out_path = out_path.with_extension("tar.gz");
+        let fd = fs::File::create(&out_path).context("failed to create fallback output file")?;
+        let enc = gz_encoder(fd, 6);
+        let mut tar = tar::Builder::new(enc);
+        append_tree(&mut tar, &tree, Path::new(".")).context("failed to append appdir")?;
+        tar.into_inner()?.finish().context("failed to finish tar")?;
+
+        status!("Wrote AppDir archive fallback at {} (not an AppImage)", out_path.display());
+        Ok(vec![Artifact { path: out_path }])
+    }
+}
+
+/// The AppDir: the shared `/usr` layout plus the AppRun entry point, desktop file and icon next to it.
+fn appdir_tree(ctx: &Context) -> Result<StagedTree> {
+    let config = ctx.config;
+    let mut tree = stage(ctx)?;
+    let bin_name = bin_name(ctx);
+    tree.add_symlink("AppRun", format!("usr/bin/{}", bin_name));
+    tree.add_file(
+        format!("{}.desktop", config.name),
+        Source::Bytes(desktop_file(config, &config.display_name, &bin_name).into_bytes()),
+        0o644,
+    );
+    if let Some(icon) = largest_icon(&tree) {
+        tree.add_file(format!("{}.png", config.name), icon, 0o644);
     }
+    Ok(tree)
+}
 
-    // Fallback: create a tar.gz snapshot of the AppDir (note: not a proper AppImage)
-    let fd = fs::File::create(&out_path.with_extension("tar.gz")).expect("failed to create fallback output file");
-    let enc = flate2::write::GzEncoder::new(fd, flate2::Compression::default());
-    let mut tar = tar::Builder::new(enc);
-    tar.append_dir_all("./", &appdir).expect("failed to append appdir");
-    tar.finish().expect("failed to finish tar");
+fn out_path(ctx: &Context) -> PathBuf {
+    let version = ctx.config.version.to_string();
+    ctx.artifact_path("standalone", "{name}-{version}-{arch}", &version, ctx.arch.appimage(), ".AppImage")
+}
 
-    println!("Wrote AppDir archive fallback at {} (not an AppImage)", out_path.with_extension("tar.gz").display());
-}
\ No newline at end of file
+/// The biggest `usr/share/icons/hicolor/<w>x<h>/apps/*.png` in the tree.
+fn largest_icon(tree: &StagedTree) -> Option<Source> {
+    tree.entries()
+        .filter_map(|(path, entry)| {
+            let size = path.strip_prefix("usr/share/icons/hicolor").ok()?.iter().next()?;
+            let width: u32 = size.to_str()?.split('x').next()?.parse().ok()?;
+            match &entry.kind {
+                EntryKind::File(source) => Some((width, source.clone())),
+                _ => None,
+            }
+        })
+        .max_by_key(|(width, _)| *width)
+        .map(|(_, source)| source)
+}
@@ -0,0 +1,163 @@
+use std::io::{self, Write};
+use std::ops::RangeInclusive;
+use anyhow::{bail, Result};
+use xz2::stream::{Check, MtStreamBuilder};
+use xz2::write::XzEncoder;
+use crate::config::{Compression, FormatConfig};
+
+/// Valid levels and the level used when only the algorithm is configured.
+fn levels(compression: Compression) -> (RangeInclusive<i32>, i32) {
+    match compression {
+        Compression::Gzip => (0..=9, 6),
+        Compression::Xz => (0..=9, 6),
+        Compression::Zstd => (1..=22, 3),
+        Compression::Bzip2 => (1..=9, 9),
+        Compression::Uncompressed => (0..=0, 0),
+    }
+}
+
+/// Algorithm and level of `format` from its configuration. The first entry of
+/// `supported` is the default algorithm, used with `default_level`.
+pub fn resolve(
+    format: &str,
+    config: &FormatConfig,
+    supported: &[Compression],
+    default_level: i32,
+) -> Result<(Compression, i32)> {
+    let compression = config.compression.unwrap_or(supported[0]);
+    if !supported.contains(&compression) {
+        bail!(
+            "{} does not support {} compression, use one of: {}",
+            format,
+            compression.name(),
+            supported.iter().map(|c| c.name()).collect::<Vec<_>>().join(", ")
+        );
+    }
+    let (range, level) = levels(compression);
+    let level = match config.compression_level {
+        Some(level) => level,
+        None if config.compression.is_none() => default_level,
+        None => level,
+    };
+    if !range.contains(&level) {
+        bail!(
+            "compression-level {} of {} is out of range for {} ({}-{})",
+            level,
+            format,
+            compression.name(),
+            range.start(),
+            range.end()
+        );
+    }
+    Ok((compression, level))
+}
+
+/// Algorithm of a secondary member of `format`, e.g. the `control.tar` of a .deb, set by
+/// `setting`, at that algorithm's default level. The first entry of `supported` is the default.
+pub fn resolve_member(
+    format: &str,
+    setting: &str,
+    compression: Option<Compression>,
+    supported: &[Compression],
+) -> Result<(Compression, i32)> {
+    let compression = compression.unwrap_or(supported[0]);
+    if !supported.contains(&compression) {
+        bail!(
+            "{} of {} does not support {} compression, use one of: {}",
+            setting,
+            format,
+            compression.name(),
+            supported.iter().map(|c| c.name()).collect::<Vec<_>>().join(", ")
+        );
+    }
+    Ok((compression, levels(compression).1))
+}
+
+/// Multi-threaded zstd encoder.
+pub fn zstd_encoder<W: Write>(writer: W, level: i32, threads: u32) -> io::Result<zstd::Encoder<'static, W>> {
+    let mut encoder = zstd::Encoder::new(writer, level)?;
+    encoder.multithread(threads)?;
+    Ok(encoder)
+}
+
+/// Multi-threaded xz encoder. Block sizes follow the preset, so the output does not
+/// depend on the number of threads.
+pub fn xz_encoder<W: Write>(writer: W, level: u32, threads: u32) -> io::Result<XzEncoder<W>> {
+    let stream = MtStreamBuilder::new()
+        .preset(level)
+        .threads(threads)
+        .check(Check::Crc64)
+        .encoder()?;
+    Ok(XzEncoder::new_stream(writer, stream))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+    use std::thread;
+    use std::time::Instant;
+    use super::*;
+
+    /// Binary-like data: stretches of noise mixed with repeated text, roughly as compressible as an executable.
+    fn payload(len: usize) -> Vec<u8> {
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut data = Vec::with_capacity(len);
+        while data.len() < len {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            if state.is_multiple_of(3) {
+                data.extend_from_slice(b"slint::ComponentHandle::run(&self) -> Result<(), PlatformError>\0");
+            } else {
+                data.extend_from_slice(&state.to_le_bytes());
+            }
+        }
+        data.truncate(len);
+        data
+    }
+
+    fn report(name: &str, input: usize, output: usize, start: Instant) {
+        let secs = start.elapsed().as_secs_f64();
+        eprintln!(
+            "{:<16} {:>8.1} MiB/s  ratio {:.3}",
+            name,
+            input as f64 / secs / 1024.0 / 1024.0,
+            output as f64 / input as f64
+        );
+    }
+
+    #[test]
+    #[ignore = "benchmark; run with `cargo test --release -- --ignored --nocapture`"]
+    fn benchmark_compression() {
+        let data = payload(64 * 1024 * 1024);
+        let cpus = thread::available_parallelism().map(|n| n.get() as u32).unwrap_or(1);
+
+        let start = Instant::now();
+        let mut single = zstd::Encoder::new(Vec::new(), 3).unwrap();
+        single.write_all(&data).unwrap();
+        let single = single.finish().unwrap();
+        report("zstd -3", data.len(), single.len(), start);
+
+        let start = Instant::now();
+        let mut multi = zstd_encoder(Vec::new(), 3, cpus).unwrap();
+        multi.write_all(&data).unwrap();
+        let multi = multi.finish().unwrap();
+        report("zstd -3 mt", data.len(), multi.len(), start);
+        assert_eq!(zstd::decode_all(multi.as_slice()).unwrap(), data);
+
+        let start = Instant::now();
+        let mut single = XzEncoder::new(Vec::new(), 6);
+        single.write_all(&data).unwrap();
+        let single = single.finish().unwrap();
+        report("xz -6", data.len(), single.len(), start);
+
+        let start = Instant::now();
+        let mut multi = xz_encoder(Vec::new(), 6, cpus).unwrap();
+        multi.write_all(&data).unwrap();
+        let multi = multi.finish().unwrap();
+        report("xz -6 mt", data.len(), multi.len(), start);
+        let mut decoded = Vec::new();
+        xz2::read::XzDecoder::new_multi_decoder(multi.as_slice()).read_to_end(&mut decoded).unwrap();
+        assert_eq!(decoded, data);
+    }
+}
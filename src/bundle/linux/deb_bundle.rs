@@ -1,158 +1,243 @@
 use std::fs;
-use std::path::Path;
-use tempfile::tempdir;
-use std::os::unix::fs::PermissionsExt;
-use crate::bundle::linux::effective_arch;
-use crate::bundle::linux::write_desktop_file;
-fn deb_arch_name(eff: &str) -> &str {
-    match eff {
-        "x86_64" => "amd64",
-        "aarch64" => "arm64",
-        "armhf" => "armhf",
-        "i386" => "i386",
-        "riscv64" => "riscv64",
-        _ => "all",
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use anyhow::{Context as _, Result};
+use crate::bundle::bundler::{Artifact, Bundler};
+use crate::bundle::linux::compress::{resolve, resolve_member, xz_encoder, zstd_encoder};
+use crate::bundle::linux::deb_control::{conffiles, control, maintainer_scripts, md5sums};
+use crate::bundle::linux::deb_docs::{changelog, copyright};
+use crate::bundle::linux::{append_tree, gz_encoder, planned_files, stage};
+use crate::bundle::plan::Plan;
+use crate::bundle::staging::{Source, StagedTree};
+use crate::bundle::Context;
+use crate::config::Compression;
+use crate::events::status;
+
+pub struct Deb;
+
+impl Bundler for Deb {
+    fn name(&self) -> &'static str {
+        "deb"
     }
-}
 
-pub fn bundle_deb() {
-    println!("Creating .deb package...");
-
-    // Read package name and version from Cargo.toml
-    let manifest = std::fs::read_to_string("Cargo.toml").expect("Failed to read Cargo.toml");
-    let package_name = manifest
-        .lines()
-        .find_map(|line| {
-            if line.trim_start().starts_with("name") {
-                line.split('=').nth(1).map(|v| v.trim().trim_matches('"').to_string())
-            } else {
-                None
-            }
-        })
-        .expect("Could not find package name in Cargo.toml");
-    let version = manifest
-        .lines()
-        .find_map(|line| {
-            if line.trim_start().starts_with("version") {
-                line.split('=').nth(1).map(|v| v.trim().trim_matches('"').to_string())
-            } else {
-                None
-            }
-        })
-        .unwrap_or_else(|| "0.1.0".to_string());
+    fn supported_hosts(&self) -> &'static [&'static str] {
+        &["linux"]
+    }
 
-    // Path to compiled binary
-    let release_bin = Path::new("target").join("release").join(&package_name);
-    if !release_bin.exists() {
-        eprintln!("Release binary not found at {}. Make sure `cargo build --release` ran successfully.", release_bin.display());
-        return;
+    fn plan(&self, ctx: &Context) -> Result<Plan> {
+        Ok(Plan {
+            files: planned_files(&stage(ctx)?),
+            tools: Vec::new(),
+            artifacts: vec![out_path(ctx)?],
+        })
     }
 
-    let tmp = tempdir().expect("failed to create tempdir");
-    let pkg_root = tmp.path().to_path_buf();
-
-    // Create standard Debian package layout
-    let debian_dir = pkg_root.join("DEBIAN");
-    fs::create_dir_all(&debian_dir).expect("failed to create DEBIAN dir");
-    let usr_bin = pkg_root.join("usr").join("bin");
-    let applications_dir = pkg_root.join("usr").join("share").join("applications");
-    let icons_root = pkg_root.join("usr").join("share").join("icons").join("hicolor");
-    fs::create_dir_all(&usr_bin).expect("failed to create usr/bin");
-    fs::create_dir_all(&applications_dir).expect("failed to create applications dir");
-    fs::create_dir_all(&icons_root).expect("failed to create icons root");
-
-    // Copy binary
-    let dest_bin = usr_bin.join(&package_name);
-    fs::copy(&release_bin, &dest_bin).expect("failed to copy binary");
-    let mut perms = fs::metadata(&dest_bin).expect("meta").permissions();
-    perms.set_mode(0o755);
-    fs::set_permissions(&dest_bin, perms).expect("failed to set permissions");
-
-    // Generate .desktop file
-    write_desktop_file(&package_name, &applications_dir).expect("failed to write desktop file");
-
-    // Install icons from ./icons folder
-    let icons_dir = Path::new("icons");
-    if icons_dir.exists() && icons_dir.is_dir() {
-        let entries = fs::read_dir(icons_dir).expect("failed to read icons dir");
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if !path.is_file() { continue; }
-                // attempt to read image to get dimensions
-                match image::open(&path) {
-                    Ok(img) => {
-                    let w = img.width();
-                    let h = img.height();
-                    let size_dir = icons_root.join(format!("{}x{}", w, h)).join("apps");
-                    fs::create_dir_all(&size_dir).expect("failed to create icon size dir");
-                    let dest = size_dir.join(format!("{}.png", package_name));
-                    // write the image as PNG to the destination to ensure correct format
-                    let mut fout = fs::File::create(&dest).expect("failed to create icon dest");
-                    img.write_to(&mut fout, image::ImageFormat::Png).expect("failed to write icon png");
-                }
-                Err(_) => {
-                    eprintln!("Warning: failed to read image {}", path.display());
-                }
-            }
+    fn bundle(&self, ctx: &Context) -> Result<Vec<Artifact>> {
+        status!("Creating .deb package...");
+
+        let (compression, level) = resolve(
+            "deb",
+            &ctx.config.deb,
+            &[Compression::Xz, Compression::Zstd, Compression::Gzip, Compression::Uncompressed],
+            6,
+        )?;
+        // Every dpkg reads a gzip control.tar, whatever the data.tar uses
+        let (control_compression, control_level) = resolve_member(
+            "deb",
+            "control-compression",
+            ctx.config.deb.control_compression,
+            &[Compression::Gzip, Compression::Xz, Compression::Zstd, Compression::Uncompressed],
+        )?;
+        let mut tree = stage(ctx)?;
+        // Resources installed under the same names take precedence
+        let doc_dir = Path::new("usr/share/doc").join(&ctx.config.name);
+        let staged = |tree: &StagedTree, name: &str| tree.entries().any(|(path, _)| path == doc_dir.join(name));
+        if !staged(&tree, "copyright") {
+            tree.add_file(doc_dir.join("copyright"), Source::Bytes(copyright(ctx.config)?.into_bytes()), 0o644);
+        }
+        if let Some(changelog) = changelog(ctx)?.filter(|_| !staged(&tree, "changelog.Debian.gz")) {
+            tree.add_file(doc_dir.join("changelog.Debian.gz"), Source::Bytes(changelog), 0o644);
+        }
+
+        let mut control_tree = StagedTree::new();
+        control_tree.mtime = tree.mtime;
+        control_tree.add_file("control", Source::Bytes(control(ctx, &tree)?.into_bytes()), 0o644);
+        control_tree.add_file("md5sums", Source::Bytes(md5sums(&tree)?.into_bytes()), 0o644);
+        let conffiles = conffiles(&tree);
+        if !conffiles.is_empty() {
+            control_tree.add_file("conffiles", Source::Bytes(conffiles.into_bytes()), 0o644);
+        }
+        for (name, script) in maintainer_scripts(ctx.config, &tree)? {
+            control_tree.add_file(name, Source::Bytes(script.into_bytes()), 0o755);
         }
-    } else {
-        eprintln!("Warning: ./icons directory not found; no icons will be installed into package.");
+
+        let out_path = out_path(ctx)?;
+        fs::create_dir_all(ctx.bundle_dir.join("deb")).context("failed to create output bundle dir")?;
+
+        // A .deb is an ar archive of debian-binary, control.tar and data.tar
+        let control_name = format!("control.tar{}", suffix(control_compression));
+        let data_name = format!("data.tar{}", suffix(compression));
+        let control_tar = compressed_tar(&control_tree, Path::new(""), control_compression, control_level, ctx.threads)
+            .with_context(|| format!("failed to create {}", control_name))?;
+        let data_tar = compressed_tar(&tree, Path::new("."), compression, level, ctx.threads)
+            .with_context(|| format!("failed to create {}", data_name))?;
+        let out = fs::File::create(&out_path).context("failed to create output deb")?;
+        let mut ar_builder = ar::Builder::new(out);
+        for (name, data) in [
+            ("debian-binary", b"2.0\n".as_slice()),
+            (control_name.as_str(), &control_tar),
+            (data_name.as_str(), &data_tar),
+        ] {
+            let mut header = ar::Header::new(name.as_bytes().to_vec(), data.len() as u64);
+            header.set_mode(0o100644);
+            header.set_mtime(tree.mtime);
+            ar_builder
+                .append(&header, data)
+                .with_context(|| format!("failed to add {} to ar", name))?;
+        }
+        ar_builder.into_inner()?.flush()?;
+
+        status!("Created {}", out_path.display());
+        Ok(vec![Artifact { path: out_path }])
     }
+}
+
+fn out_path(ctx: &Context) -> Result<PathBuf> {
+    // Debian file names leave out the epoch
+    let version = ctx.config.version.deb(None)?;
+    Ok(ctx.artifact_path("deb", "{name}_{version}_{arch}", &version, ctx.arch.deb(), ".deb"))
+}
 
-    // Create control file
-    // determine architecture string for Debian control file, respect forced arch env
-    let eff = effective_arch();
-    let arch = deb_arch_name(&eff).to_string();
-    let control = format!(
-        "Package: {pkg}\nVersion: {ver}\nSection: utils\nPriority: optional\nArchitecture: {arch}\nMaintainer: packager <packager@local>\nDescription: {pkg} packaged by slint-bundler\n",
-        pkg = package_name,
-        ver = version,
-        arch = arch
-    );
-    fs::write(debian_dir.join("control"), control).expect("failed to write control file");
-
-    // Build .deb ourselves (create data.tar.gz and control.tar.gz and then ar archive)
-    let out_dir = Path::new("target").join("release").join("bundle").join("deb");
-    fs::create_dir_all(&out_dir).expect("failed to create output bundle dir");
-    let output_deb_path = out_dir.join(format!("{}_{}_{}.deb", package_name, version, arch));
-    let control_tar_gz = tmp.path().join("control.tar.gz");
-    let data_tar_gz = tmp.path().join("data.tar.gz");
-
-    // Create control.tar.gz
-    {
-        let control_fd = fs::File::create(&control_tar_gz).expect("failed to create control.tar.gz");
-        let enc = flate2::write::GzEncoder::new(control_fd, flate2::Compression::default());
-        let mut tar = tar::Builder::new(enc);
-        // add DEBIAN/control
-        tar.append_path_with_name(debian_dir.join("control"), "control").expect("failed to append control");
-        // finish and flush
-        tar.finish().expect("failed to finish control tar");
+/// Member name suffix dpkg expects for `compression`.
+fn suffix(compression: Compression) -> &'static str {
+    match compression {
+        Compression::Gzip => ".gz",
+        Compression::Xz => ".xz",
+        Compression::Zstd => ".zst",
+        Compression::Bzip2 => ".bz2",
+        Compression::Uncompressed => "",
     }
+}
 
-    // Create data.tar.gz (everything under pkg_root except DEBIAN)
-    {
-        let data_fd = fs::File::create(&data_tar_gz).expect("failed to create data.tar.gz");
-        let enc = flate2::write::GzEncoder::new(data_fd, flate2::Compression::default());
-        let mut tar = tar::Builder::new(enc);
-        // append usr/ and other dirs
-        let usr_dir = pkg_root.join("usr");
-        tar.append_dir_all("./usr", &usr_dir).expect("failed to append usr dir");
-        tar.finish().expect("failed to finish data tar");
+/// `tree` as a tar archive below `prefix`, compressed with `compression` at `level`.
+fn compressed_tar(
+    tree: &StagedTree,
+    prefix: &Path,
+    compression: Compression,
+    level: i32,
+    threads: u32,
+) -> io::Result<Vec<u8>> {
+    let mut tar = tar::Builder::new(Vec::new());
+    append_tree(&mut tar, tree, prefix)?;
+    let tar = tar.into_inner()?;
+    match compression {
+        Compression::Gzip => {
+            let mut enc = gz_encoder(Vec::new(), level as u32);
+            enc.write_all(&tar)?;
+            enc.finish()
+        }
+        Compression::Xz => {
+            let mut enc = xz_encoder(Vec::new(), level as u32, threads)?;
+            enc.write_all(&tar)?;
+            enc.finish()
+        }
+        Compression::Zstd => {
+            let mut enc = zstd_encoder(Vec::new(), level, threads)?;
+            enc.write_all(&tar)?;
+            enc.finish()
+        }
+        Compression::Uncompressed => Ok(tar),
+        Compression::Bzip2 => unreachable!("resolve rejects bzip2 for deb"),
     }
+}
 
-    // Write the debian-binary (must contain '2.0' and newline)
-    let debian_binary = tmp.path().join("debian-binary");
-    fs::write(&debian_binary, b"2.0\n").expect("failed to write debian-binary");
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+    use super::*;
+    use crate::bundle::linux::tests::config;
+    use crate::bundle::tests::context;
 
-    // Create ar archive: debian-binary, control.tar.gz, data.tar.gz
-    {
-        let out = fs::File::create(&output_deb_path).expect("failed to create output deb");
-        let mut ar_builder = ar::Builder::new(out);
-        ar_builder.append_path(&debian_binary).expect("failed to add debian-binary to ar");
-        ar_builder.append_path(&control_tar_gz).expect("failed to add control.tar.gz to ar");
-        ar_builder.append_path(&data_tar_gz).expect("failed to add data.tar.gz to ar");
-        // ar::Builder finishes when dropped
+    /// Unpack a tar member and return its paths, without a leading `./`, with their contents.
+    fn untar(name: &str, data: &[u8]) -> Vec<(String, Vec<u8>)> {
+        let tar = match name.rsplit_once(".tar").unwrap().1 {
+            ".gz" => {
+                let mut tar = Vec::new();
+                flate2::read::GzDecoder::new(data).read_to_end(&mut tar).unwrap();
+                tar
+            }
+            ".xz" => {
+                let mut tar = Vec::new();
+                xz2::read::XzDecoder::new(data).read_to_end(&mut tar).unwrap();
+                tar
+            }
+            ".zst" => zstd::decode_all(data).unwrap(),
+            "" => data.to_vec(),
+            other => panic!("unexpected suffix {:?}", other),
+        };
+        let mut archive = tar::Archive::new(tar.as_slice());
+        archive
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let mut entry = entry.unwrap();
+                let path = entry.path().unwrap().to_string_lossy().trim_start_matches("./").to_string();
+                let mut data = Vec::new();
+                entry.read_to_end(&mut data).unwrap();
+                (path, data)
+            })
+            .collect()
     }
 
-    println!("Created {}", output_deb_path.display());
-}
\ No newline at end of file
+    #[test]
+    fn ar_members_round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let binary = tmp.path().join("repro");
+        fs::write(&binary, b"\x7fELF not really").unwrap();
+
+        // Data compression, control compression and the member suffixes they give
+        for (i, (compression, control_compression, control_suffix, data_suffix)) in [
+            (Compression::Gzip, None, ".gz", ".gz"),
+            (Compression::Xz, None, ".gz", ".xz"),
+            (Compression::Zstd, None, ".gz", ".zst"),
+            (Compression::Uncompressed, Some(Compression::Uncompressed), "", ""),
+            (Compression::Zstd, Some(Compression::Xz), ".xz", ".zst"),
+            (Compression::Xz, Some(Compression::Zstd), ".zst", ".xz"),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let mut config = config();
+            config.deb.compression = Some(compression);
+            config.deb.control_compression = control_compression;
+            let mut ctx = context(&config, tmp.path());
+            ctx.bundle_dir = tmp.path().join(i.to_string());
+            ctx.threads = 2;
+            let artifacts = Deb.bundle(&ctx).unwrap();
+
+            let mut archive = ar::Archive::new(fs::File::open(&artifacts[0].path).unwrap());
+            let mut members = Vec::new();
+            while let Some(entry) = archive.next_entry() {
+                let mut entry = entry.unwrap();
+                let name = String::from_utf8(entry.header().identifier().to_vec()).unwrap();
+                let mut data = Vec::new();
+                entry.read_to_end(&mut data).unwrap();
+                members.push((name, data));
+            }
+            let names: Vec<&str> = members.iter().map(|(name, _)| name.as_str()).collect();
+            let control_name = format!("control.tar{}", control_suffix);
+            let data_name = format!("data.tar{}", data_suffix);
+            assert_eq!(names, ["debian-binary", control_name.as_str(), data_name.as_str()]);
+            assert_eq!(members[0].1, b"2.0\n");
+
+            let control = untar(&members[1].0, &members[1].1);
+            let control_file = control.iter().find(|(path, _)| path == "control").unwrap();
+            assert!(String::from_utf8_lossy(&control_file.1).starts_with("Package: repro\n"));
+            assert!(control.iter().any(|(path, _)| path == "md5sums"));
+
+            let data = untar(&members[2].0, &members[2].1);
+            let bin = data.iter().find(|(path, _)| path == "usr/bin/repro").unwrap();
+            assert_eq!(bin.1, b"\x7fELF not really");
+        }
+    }
+}
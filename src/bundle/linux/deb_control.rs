@@ -0,0 +1,278 @@
+use std::fmt::Write as _;
+use std::fs;
+use anyhow::{bail, Context as _, Result};
+use md5::{Digest, Md5};
+use crate::bundle::linux::shlibs;
+use crate::bundle::staging::{EntryKind, StagedTree};
+use crate::bundle::Context;
+use crate::config::Config;
+use crate::events;
+
+/// The `control` file describing the package staged in `tree`, fields in the order
+/// Debian policy lists them.
+pub fn control(ctx: &Context, tree: &StagedTree) -> Result<String> {
+    let config = ctx.config;
+    let mut control = String::new();
+    let _ = writeln!(control, "Package: {}", config.name);
+    let _ = writeln!(control, "Version: {}", config.version.deb(config.linux.epoch)?);
+    let _ = writeln!(control, "Architecture: {}", ctx.arch.deb());
+    let maintainer = maintainer(config).unwrap_or_else(|| {
+        events::warning(format_args!(
+            "no maintainer email for the deb package, set linux.maintainer or add one to `authors`"
+        ));
+        config.publisher.clone()
+    });
+    let _ = writeln!(control, "Maintainer: {}", maintainer);
+    let _ = writeln!(control, "Installed-Size: {}", installed_size(tree)?);
+    let depends = depends(config, tree)?;
+    if !depends.is_empty() {
+        let _ = writeln!(control, "Depends: {}", depends.join(", "));
+    }
+    let _ = writeln!(control, "Section: {}", section(config));
+    let _ = writeln!(control, "Priority: {}", config.linux.priority);
+    if let Some(homepage) = &config.homepage {
+        let _ = writeln!(control, "Homepage: {}", homepage);
+    }
+    let _ = writeln!(control, "Description: {}", description(&config.description));
+    Ok(control)
+}
+
+/// `md5sums`: the checksum and path of every regular file, as `dpkg --verify` reads it.
+/// Conffiles are left out, dpkg records their checksums itself.
+pub fn md5sums(tree: &StagedTree) -> Result<String> {
+    let mut sums = String::new();
+    for (path, entry) in tree.entries() {
+        if let EntryKind::File(source) = &entry.kind {
+            if path.starts_with("etc") {
+                continue;
+            }
+            let data = source.read().with_context(|| format!("failed to read {}", path.display()))?;
+            let digest = Md5::digest(&data);
+            for byte in digest {
+                let _ = write!(sums, "{:02x}", byte);
+            }
+            let _ = writeln!(sums, "  {}", path.display());
+        }
+    }
+    Ok(sums)
+}
+
+/// `conffiles`: every regular file below `/etc`, which dpkg then keeps when the user edited it.
+pub fn conffiles(tree: &StagedTree) -> String {
+    let mut conffiles = String::new();
+    for (path, entry) in tree.entries() {
+        if path.starts_with("etc") && matches!(entry.kind, EntryKind::File(_)) {
+            let _ = writeln!(conffiles, "/{}", path.display());
+        }
+    }
+    conffiles
+}
+
+/// The maintainer scripts from `linux.scripts`, with the cache refreshes the staged desktop
+/// entries and icons need put in place of a `#DEBHELPER#` line or right after the `#!` line.
+pub fn maintainer_scripts(config: &Config, tree: &StagedTree) -> Result<Vec<(&'static str, String)>> {
+    let installs = |dir: &str| {
+        tree.entries()
+            .any(|(path, entry)| path.starts_with(dir) && matches!(entry.kind, EntryKind::File(_)))
+    };
+    let mut refresh = String::new();
+    if installs("usr/share/applications") {
+        refresh.push_str(
+            "\tif command -v update-desktop-database >/dev/null 2>&1; then\n\
+             \t\tupdate-desktop-database -q /usr/share/applications\n\
+             \tfi\n",
+        );
+    }
+    if installs("usr/share/icons/hicolor") {
+        refresh.push_str(
+            "\tif command -v gtk-update-icon-cache >/dev/null 2>&1; then\n\
+             \t\tgtk-update-icon-cache -q -t -f /usr/share/icons/hicolor\n\
+             \tfi\n",
+        );
+    }
+
+    let mut scripts = Vec::new();
+    for name in ["preinst", "postinst", "prerm", "postrm"] {
+        let snippet = match name {
+            "postinst" if !refresh.is_empty() => format!("if [ \"$1\" = configure ]; then\n{}fi\n", refresh),
+            "postrm" if !refresh.is_empty() => format!("if [ \"$1\" = remove ]; then\n{}fi\n", refresh),
+            _ => String::new(),
+        };
+        let script = match config.linux.scripts.iter().find(|(n, _)| *n == name) {
+            Some((_, path)) => {
+                let script =
+                    fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+                insert_snippet(&script, &snippet)
+                    .with_context(|| format!("invalid maintainer script {}", path.display()))?
+            }
+            None if !snippet.is_empty() => format!("#!/bin/sh\nset -e\n\n{}", snippet),
+            None => continue,
+        };
+        scripts.push((name, script));
+    }
+    Ok(scripts)
+}
+
+/// Put `snippet` in place of the `#DEBHELPER#` line of `script`, else after its `#!` line.
+fn insert_snippet(script: &str, snippet: &str) -> Result<String> {
+    let token = |line: &str| line.trim() == "#DEBHELPER#";
+    if script.lines().any(token) {
+        let lines: Vec<&str> = script
+            .lines()
+            .map(|line| if token(line) { snippet.trim_end() } else { line })
+            .collect();
+        return Ok(lines.join("\n") + "\n");
+    }
+    let Some((shebang, rest)) = script.split_once('\n').filter(|(first, _)| first.starts_with("#!")) else {
+        bail!("the script does not start with a #! line");
+    };
+    if snippet.is_empty() {
+        return Ok(script.to_string());
+    }
+    Ok(format!("{}\n{}{}", shebang, snippet, rest))
+}
+
+/// `linux.depends` followed by the packages of the linked libraries it does not name.
+fn depends(config: &Config, tree: &StagedTree) -> Result<Vec<String>> {
+    let mut depends = config.linux.depends.clone();
+    if config.linux.auto_depends {
+        let package_name = |dep: &str| dep.split([' ', '(']).next().unwrap_or_default().to_string();
+        let named: Vec<String> = depends.iter().map(|d| package_name(d)).collect();
+        let detected = shlibs::deb_depends(config, &shlibs::needed(tree)?);
+        depends.extend(detected.into_iter().filter(|d| !named.contains(d)));
+    }
+    Ok(depends)
+}
+
+/// Size in KiB as `dpkg-gencontrol` computes it: files rounded up to whole KiB,
+/// directories and symlinks one each.
+fn installed_size(tree: &StagedTree) -> Result<u64> {
+    let mut size = 0;
+    for (path, entry) in tree.entries() {
+        size += match &entry.kind {
+            EntryKind::File(source) => source
+                .size()
+                .with_context(|| format!("failed to read {}", path.display()))?
+                .div_ceil(1024),
+            EntryKind::Dir | EntryKind::Symlink(_) => 1,
+        };
+    }
+    Ok(size)
+}
+
+/// `linux.maintainer`, else the first author with an email address.
+pub fn maintainer(config: &Config) -> Option<String> {
+    config
+        .linux
+        .maintainer
+        .clone()
+        .or_else(|| config.authors.iter().find(|a| a.contains('<')).cloned())
+}
+
+/// `linux.section`, else the Debian section of the first category that has one.
+fn section(config: &Config) -> String {
+    if let Some(section) = &config.linux.section {
+        return section.clone();
+    }
+    config
+        .categories
+        .iter()
+        .find_map(|category| {
+            Some(match category.as_str() {
+                "Audio" => "sound",
+                "AudioVideo" | "Video" => "video",
+                "Development" => "devel",
+                "Education" => "education",
+                "Game" => "games",
+                "Graphics" => "graphics",
+                "Network" => "net",
+                "Office" => "text",
+                "Science" => "science",
+                "Settings" | "System" => "admin",
+                "Utility" => "utils",
+                _ => return None,
+            })
+        })
+        .unwrap_or("utils")
+        .to_string()
+}
+
+/// The first line as synopsis, further lines as the extended description: indented by
+/// one space, with blank lines written as ` .`.
+fn description(text: &str) -> String {
+    let mut lines = text.trim().lines();
+    let mut description = lines.next().unwrap_or_default().trim().to_string();
+    for line in lines.map(str::trim) {
+        if line.is_empty() {
+            description.push_str("\n .");
+        } else {
+            description.push_str("\n ");
+            description.push_str(line);
+        }
+    }
+    description
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use super::*;
+    use crate::bundle::linux::tests::config;
+    use crate::bundle::staging::Source;
+    use crate::bundle::tests::context;
+    use crate::bundle::Arch;
+
+    fn tree() -> StagedTree {
+        let mut tree = StagedTree::new();
+        tree.add_file("usr/bin/repro", Source::Bytes(vec![b'x'; 1500]), 0o755);
+        tree.add_symlink("usr/bin/repro-alias", "repro");
+        tree.add_file("usr/share/doc/repro/README", Source::Bytes(b"Read me\n".to_vec()), 0o644);
+        tree.add_file("etc/repro.conf", Source::Bytes(b"answer=42\n".to_vec()), 0o644);
+        tree
+    }
+
+    #[test]
+    fn control_fields() {
+        let mut config = config();
+        config.description = "Synopsis line\nFirst paragraph\ncontinued.\n\nSecond paragraph.".to_string();
+        config.categories = vec!["Graphics".to_string()];
+        config.linux.depends = vec!["libexample1 (>= 1.2)".to_string()];
+        let mut ctx = context(&config, Path::new(""));
+        ctx.target = "aarch64-unknown-linux-gnu".to_string();
+        ctx.arch = Arch::Aarch64;
+        assert_eq!(
+            control(&ctx, &tree()).unwrap(),
+            "Package: repro
+Version: 1.0.0
+Architecture: arm64
+Maintainer: Example <example@example.com>
+Installed-Size: 11
+Depends: libexample1 (>= 1.2)
+Section: graphics
+Priority: optional
+Description: Synopsis line
+ First paragraph
+ continued.
+ .
+ Second paragraph.
+"
+        );
+    }
+
+    #[test]
+    fn installed_size_counts_kib() {
+        // 2 KiB for the binary, 1 each for the two small files, the symlink and the six directories
+        assert_eq!(installed_size(&tree()).unwrap(), 11);
+        assert_eq!(installed_size(&StagedTree::new()).unwrap(), 0);
+    }
+
+    #[test]
+    fn md5sums_skip_conffiles() {
+        assert_eq!(
+            md5sums(&tree()).unwrap(),
+            "fae20da2fa865c572238d397a9b8dffc  usr/bin/repro\n\
+             658aa3e7426a8afdf434d6cea6d3b7e3  usr/share/doc/repro/README\n"
+        );
+        assert_eq!(conffiles(&tree()), "/etc/repro.conf\n");
+    }
+}
@@ -0,0 +1,389 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::io::Write as _;
+use anyhow::{Context as _, Result};
+use regex::Regex;
+use crate::bundle::linux::deb_control::maintainer;
+use crate::bundle::linux::gz_encoder;
+use crate::bundle::Context;
+use crate::config::{Config, Version};
+use crate::events;
+
+/// SPDX identifiers with their DEP-5 short name and file in `/usr/share/common-licenses`.
+const COMMON_LICENSES: &[(&str, &str, &str)] = &[
+    ("Apache-2.0", "Apache-2.0", "Apache-2.0"),
+    ("MPL-2.0", "MPL-2.0", "MPL-2.0"),
+    ("CC0-1.0", "CC0-1.0", "CC0-1.0"),
+    ("GPL-2.0", "GPL-2", "GPL-2"),
+    ("GPL-2.0-only", "GPL-2", "GPL-2"),
+    ("GPL-2.0-or-later", "GPL-2+", "GPL-2"),
+    ("GPL-3.0", "GPL-3", "GPL-3"),
+    ("GPL-3.0-only", "GPL-3", "GPL-3"),
+    ("GPL-3.0-or-later", "GPL-3+", "GPL-3"),
+    ("LGPL-2.1", "LGPL-2.1", "LGPL-2.1"),
+    ("LGPL-2.1-only", "LGPL-2.1", "LGPL-2.1"),
+    ("LGPL-2.1-or-later", "LGPL-2.1+", "LGPL-2.1"),
+    ("LGPL-3.0", "LGPL-3", "LGPL-3"),
+    ("LGPL-3.0-only", "LGPL-3", "LGPL-3"),
+    ("LGPL-3.0-or-later", "LGPL-3+", "LGPL-3"),
+];
+
+/// Machine-readable `copyright` file (DEP-5) covering the whole package with the Cargo
+/// `license`, and the license text when there is a license file.
+pub fn copyright(config: &Config) -> Result<String> {
+    let mut copyright = String::new();
+    let _ = writeln!(copyright, "Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/");
+    let _ = writeln!(copyright, "Upstream-Name: {}", config.name);
+    if let Some(contact) = maintainer(config) {
+        let _ = writeln!(copyright, "Upstream-Contact: {}", contact);
+    }
+    if let Some(homepage) = &config.homepage {
+        let _ = writeln!(copyright, "Source: {}", homepage);
+    }
+
+    let holders = if config.authors.is_empty() {
+        vec![config.publisher.clone()]
+    } else {
+        config.authors.clone()
+    };
+    let license = match &config.license {
+        Some(license) => dep5_expression(license),
+        None => {
+            if config.license_file.is_none() {
+                events::warning("no license for the deb copyright file, set `license` in Cargo.toml");
+            }
+            "Proprietary".to_string()
+        }
+    };
+    let _ = writeln!(copyright, "\nFiles: *");
+    let _ = writeln!(copyright, "Copyright: {}", holders.join("\n "));
+    let _ = writeln!(copyright, "License: {}", license);
+    if let Some(file) = &config.license_file {
+        let text = fs::read_to_string(file).with_context(|| format!("failed to read {}", file.display()))?;
+        copyright.push_str(&indent(&text));
+        return Ok(copyright);
+    }
+
+    // Without a license file, point to the copies Debian ships and warn about the rest
+    let mut missing = Vec::new();
+    for id in config.license.iter().flat_map(|l| license_ids(l)) {
+        match COMMON_LICENSES.iter().find(|(spdx, _, _)| *spdx == id) {
+            Some((_, name, file)) => {
+                let paragraph = format!("\nLicense: {}\n", name);
+                if !copyright.contains(&paragraph) {
+                    copyright.push_str(&paragraph);
+                    let _ = writeln!(
+                        copyright,
+                        " On Debian systems, the full text of this license can be found in\n \
+                         /usr/share/common-licenses/{}.",
+                        file
+                    );
+                }
+            }
+            None => missing.push(id),
+        }
+    }
+    if !missing.is_empty() {
+        events::warning(format_args!(
+            "no license text for {} in the deb copyright file, add a LICENSE file or set `license-file`",
+            missing.join(", ")
+        ));
+    }
+    Ok(copyright)
+}
+
+/// `changelog.Debian.gz` from the Markdown changelog in `linux.changelog`, one entry per
+/// `## <version>` section. An entry for the package version is added when the changelog
+/// has none, as dpkg expects the first entry to match.
+pub fn changelog(ctx: &Context) -> Result<Option<Vec<u8>>> {
+    let config = ctx.config;
+    let Some(path) = &config.linux.changelog else {
+        return Ok(None);
+    };
+    let markdown = fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let maintainer = maintainer(config).unwrap_or_else(|| config.publisher.clone());
+    let version = config.version.deb(config.linux.epoch)?;
+
+    let mut releases = releases(&markdown);
+    let current = releases
+        .iter()
+        .position(|release| Version::parse(&release.version).is_ok_and(|v| v == config.version));
+    for release in &mut releases {
+        if let Some(deb) = Version::parse(&release.version).ok().and_then(|v| v.deb(None).ok()) {
+            release.version = deb;
+        }
+    }
+    match current {
+        Some(i) => releases[i].version = version,
+        None => {
+            let change = match config.version.snapshot {
+                Some(_) => "New upstream snapshot.",
+                None => "New upstream release.",
+            };
+            let lines = vec![format!("  * {}", change)];
+            releases.insert(0, Release { version, date: None, lines });
+        }
+    }
+
+    let mut changelog = String::new();
+    for release in releases {
+        let _ = writeln!(changelog, "{} ({}) unstable; urgency=medium\n", config.name, release.version);
+        for line in &release.lines {
+            let _ = writeln!(changelog, "{}", line);
+        }
+        if release.lines.is_empty() {
+            let _ = writeln!(changelog, "  * New upstream release.");
+        }
+        let date = release.date.unwrap_or(ctx.mtime);
+        let _ = writeln!(changelog, "\n -- {}  {}\n", maintainer, rfc2822(date));
+    }
+
+    // Debian compresses changelogs with gzip -9
+    let mut gz = gz_encoder(Vec::new(), 9);
+    gz.write_all(changelog.as_bytes())?;
+    Ok(Some(gz.finish()?))
+}
+
+/// One `## <version>` section of a Markdown changelog.
+struct Release {
+    version: String,
+    /// From a `YYYY-MM-DD` date in the heading.
+    date: Option<u64>,
+    /// Changelog entry lines: `  * ` items, `    - ` for nested ones.
+    lines: Vec<String>,
+}
+
+/// The release sections of a Keep a Changelog style file; `Unreleased` and headings that
+/// do not start with a version are skipped. `### Added` style headings become items with
+/// their entries nested below.
+fn releases(markdown: &str) -> Vec<Release> {
+    let date = Regex::new(r"(\d{4})-(\d{2})-(\d{2})").unwrap();
+    // `[1.0.0]: https://...` targets of the heading links
+    let link_reference = Regex::new(r"^\[[^\]]+\]:\s").unwrap();
+    let mut releases: Vec<Release> = Vec::new();
+    let mut in_release = false;
+    let mut nested = false;
+    let mut continuation = String::new();
+    for line in markdown.lines() {
+        if let Some(heading) = line.strip_prefix("## ") {
+            let version = heading.split_whitespace().next().unwrap_or_default();
+            let version = version.trim_matches(['[', ']']).trim_start_matches('v');
+            in_release = version.starts_with(|c: char| c.is_ascii_digit());
+            nested = false;
+            continuation.clear();
+            if in_release {
+                let date = date.captures(heading).map(|c| {
+                    let part = |i: usize| c[i].parse::<i64>().unwrap_or(1);
+                    days_from_civil(part(1), part(2), part(3)).max(0) as u64 * 86400
+                });
+                releases.push(Release { version: version.to_string(), date, lines: Vec::new() });
+            }
+            continue;
+        }
+        let Some(release) = releases.last_mut().filter(|_| in_release) else {
+            continue;
+        };
+        if line.starts_with("# ") {
+            in_release = false;
+        } else if link_reference.is_match(line) {
+            continue;
+        } else if let Some(heading) = line.strip_prefix("### ") {
+            release.lines.push(format!("  * {}", heading.trim()));
+            nested = true;
+            continuation.clear();
+        } else if !line.trim().is_empty() {
+            let text = line.trim_start();
+            match text.strip_prefix("- ").or_else(|| text.strip_prefix("* ")) {
+                Some(item) => {
+                    let depth = (line.len() - text.len()) / 2 + usize::from(nested);
+                    let indent = "  ".repeat(depth + 1);
+                    let bullet = ['*', '-', '+'][depth.min(2)];
+                    release.lines.push(format!("{}{} {}", indent, bullet, item));
+                    continuation = format!("{}  ", indent);
+                }
+                // Wrapped item text lines up with the item, other text with the top level items
+                None if continuation.is_empty() => release.lines.push(format!("    {}", text)),
+                None => release.lines.push(format!("{}{}", continuation, text)),
+            }
+        }
+    }
+    releases
+}
+
+/// SPDX `MIT OR Apache-2.0` (or the older `MIT/Apache-2.0`) -> DEP-5 `MIT or Apache-2.0`,
+/// using Debian's names for the GNU licenses.
+fn dep5_expression(spdx: &str) -> String {
+    spdx.replace('/', " OR ")
+        .split_whitespace()
+        .map(|token| match token {
+            "OR" => "or".to_string(),
+            "AND" => "and".to_string(),
+            "WITH" => "with".to_string(),
+            token => {
+                let id = token.trim_matches(['(', ')']);
+                match COMMON_LICENSES.iter().find(|(spdx, _, _)| *spdx == id) {
+                    Some((_, name, _)) => token.replace(id, name),
+                    None => token.to_string(),
+                }
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The license identifiers of an SPDX expression, without operators and exceptions.
+fn license_ids(spdx: &str) -> Vec<String> {
+    let spdx = spdx.replace('/', " OR ");
+    let mut ids = Vec::new();
+    let mut tokens = spdx.split_whitespace().map(|t| t.trim_matches(['(', ')']));
+    while let Some(token) = tokens.next() {
+        match token {
+            "OR" | "AND" => {}
+            "WITH" => {
+                tokens.next();
+            }
+            id => ids.push(id.to_string()),
+        }
+    }
+    ids
+}
+
+/// A field value continued over several lines: each indented by one space, blank lines as ` .`.
+fn indent(text: &str) -> String {
+    let mut indented = String::new();
+    for line in text.trim().lines() {
+        match line.trim_end() {
+            "" => indented.push_str(" .\n"),
+            line => {
+                let _ = writeln!(indented, " {}", line);
+            }
+        }
+    }
+    indented
+}
+
+/// `Tue, 14 Nov 2023 22:13:20 +0000` for seconds since the Unix epoch.
+fn rfc2822(secs: u64) -> String {
+    const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+    const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+    let days = (secs / 86400) as i64;
+    let (year, month, day) = civil_from_days(days);
+    let time = secs % 86400;
+    format!(
+        "{}, {:02} {} {} {:02}:{:02}:{:02} +0000",
+        WEEKDAYS[days.rem_euclid(7) as usize],
+        day,
+        MONTHS[month as usize - 1],
+        year,
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Year, month and day of a day count since 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bundle::linux::tests::config;
+
+    #[test]
+    fn markdown_releases() {
+        let markdown = "# Changelog
+
+## [Unreleased]
+- Not released yet
+
+## [1.1.0] - 2024-02-29
+### Added
+- Dark mode
+  with wrapped text
+- Nested
+  - child
+
+## v1.0.0
+Initial release.
+
+[1.1.0]: https://example.com/compare/v1.0.0...v1.1.0
+";
+        let releases = releases(markdown);
+        assert_eq!(releases.len(), 2);
+        assert_eq!(releases[0].version, "1.1.0");
+        assert_eq!(releases[0].date, Some(1_709_164_800));
+        assert_eq!(
+            releases[0].lines,
+            ["  * Added", "    - Dark mode", "      with wrapped text", "    - Nested", "      + child"]
+        );
+        assert_eq!(releases[1].version, "1.0.0");
+        assert_eq!(releases[1].date, None);
+        assert_eq!(releases[1].lines, ["    Initial release."]);
+    }
+
+    #[test]
+    fn license_expressions() {
+        assert_eq!(dep5_expression("MIT OR Apache-2.0"), "MIT or Apache-2.0");
+        assert_eq!(dep5_expression("MIT/Apache-2.0"), "MIT or Apache-2.0");
+        assert_eq!(
+            dep5_expression("(GPL-3.0-or-later WITH Classpath-exception-2.0) AND MIT"),
+            "(GPL-3+ with Classpath-exception-2.0) and MIT"
+        );
+        assert_eq!(license_ids("(GPL-3.0-or-later WITH Classpath-exception-2.0) AND MIT"), ["GPL-3.0-or-later", "MIT"]);
+    }
+
+    #[test]
+    fn copyright_points_to_common_licenses() {
+        let mut config = config();
+        config.license = Some("MIT OR GPL-2.0-or-later".to_string());
+        let copyright = copyright(&config).unwrap();
+        assert!(copyright.contains("Upstream-Name: repro\n"), "{}", copyright);
+        assert!(copyright.contains("Copyright: Example <example@example.com>\nLicense: MIT or GPL-2+\n"), "{}", copyright);
+        assert!(copyright.ends_with(
+            "\nLicense: GPL-2+\n On Debian systems, the full text of this license can be found in\n /usr/share/common-licenses/GPL-2.\n"
+        ), "{}", copyright);
+    }
+
+    #[test]
+    fn copyright_includes_the_license_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let file = tmp.path().join("LICENSE");
+        fs::write(&file, "Permission is granted.\n\nNo warranty.\n").unwrap();
+        let mut config = config();
+        config.license_file = Some(file);
+        let copyright = copyright(&config).unwrap();
+        assert!(copyright.ends_with("License: MIT\n Permission is granted.\n .\n No warranty.\n"), "{}", copyright);
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(rfc2822(0), "Thu, 01 Jan 1970 00:00:00 +0000");
+        assert_eq!(rfc2822(1_700_000_000), "Tue, 14 Nov 2023 22:13:20 +0000");
+        assert_eq!(rfc2822(19782 * 86400 + 3661), "Thu, 29 Feb 2024 01:01:01 +0000");
+        for (date, days) in [((1970, 1, 1), 0), ((2000, 3, 1), 11017), ((2024, 2, 29), 19782), ((1969, 12, 31), -1)] {
+            assert_eq!(days_from_civil(date.0, date.1, date.2), days);
+            assert_eq!(civil_from_days(days), date);
+        }
+    }
+}
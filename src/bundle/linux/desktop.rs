@@ -1,33 +1,31 @@
-use std::io::{self};
-use std::path::Path;
-use std::fs;
+use crate::config::Config;
 
 
-/// Converts an executable name like "my_app" to "My App"
-fn prettify_name(exe_name: &str) -> String {
-    exe_name
-        .split('_')
-        .map(|word| {
-            let mut chars = word.chars();
-            match chars.next() {
-                Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
-                None => String::new(),
-            }
-        })
-        .collect::<Vec<_>>()
-        .join(" ")
+/// Contents of the desktop file launching `bin_name`, shown as `name`. Values end at the
+/// line break, so `Comment` is the description's first line.
+pub fn desktop_file(config: &Config, name: &str, bin_name: &str) -> String {
+    format!(
+        "[Desktop Entry]\nType=Application\nName={name}\nComment={comment}\nExec={bin}\nIcon={icon}\nTerminal=false\nCategories={categories};\n",
+        name = name,
+        comment = config.description.trim().lines().next().unwrap_or_default().trim(),
+        bin = bin_name,
+        icon = config.name,
+        categories = config.categories.join(";")
+    )
 }
 
 
-/// Write a desktop file for a binary name into the given output directory.
-pub fn write_desktop_file(bin_name: &str, output_dir: &Path) -> io::Result<()> {
-    let desktop_content = format!(
-        "[Desktop Entry]\nType=Application\nName={name}\nExec={bin}\nIcon={bin}\nTerminal=false\nCategories=Utility;\n",
-        name = prettify_name(bin_name),
-        bin = bin_name
-    );
-    let desktop_file_path = output_dir.join(format!("{}.desktop", bin_name));
-    fs::write(desktop_file_path, desktop_content.as_bytes())?;
-    Ok(())
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bundle::linux::tests::config;
 
+    #[test]
+    fn comment_is_the_first_line() {
+        let mut config = config();
+        config.description = "A reproducible app\n\nIt has a longer description.\n".to_string();
+        let desktop = desktop_file(&config, "Repro", "repro");
+        assert!(desktop.contains("\nComment=A reproducible app\nExec=repro\n"), "{}", desktop);
+        assert_eq!(desktop.lines().count(), 8);
+    }
+}
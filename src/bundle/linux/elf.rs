@@ -0,0 +1,89 @@
+/// Dynamic linking information of an ELF file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Dynamic {
+    /// `DT_NEEDED` sonames in link order, e.g. `libfontconfig.so.1`.
+    pub needed: Vec<String>,
+    /// Built for a 64-bit ABI, which rpm marks with `()(64bit)`.
+    pub is_64bit: bool,
+}
+
+const SHT_DYNAMIC: u32 = 6;
+const DT_NULL: u64 = 0;
+const DT_NEEDED: u64 = 1;
+
+/// Read the `.dynamic` section of an ELF file; `None` for anything else, statically linked
+/// executables and files without section headers.
+pub fn dynamic(data: &[u8]) -> Option<Dynamic> {
+    if !data.starts_with(b"\x7fELF") {
+        return None;
+    }
+    let is_64bit = match data.get(4)? {
+        1 => false,
+        2 => true,
+        _ => return None,
+    };
+    let big_endian = *data.get(5)? == 2;
+    let read = |offset: usize, size: usize| -> Option<u64> {
+        let bytes = data.get(offset..offset.checked_add(size)?)?;
+        let mut value = 0u64;
+        for i in 0..size {
+            let byte = if big_endian { bytes[i] } else { bytes[size - 1 - i] };
+            value = value << 8 | u64::from(byte);
+        }
+        Some(value)
+    };
+    // Offsets and sizes of the header and section header fields for each class
+    let word = if is_64bit { 8 } else { 4 };
+    let (shoff, shentsize, shnum) = if is_64bit { (0x28, 0x3a, 0x3c) } else { (0x20, 0x2e, 0x30) };
+    let (sh_offset, sh_size, sh_link) = if is_64bit { (24, 32, 40) } else { (16, 20, 24) };
+
+    let table = read(shoff, word)? as usize;
+    let entry_size = read(shentsize, 2)? as usize;
+    let count = read(shnum, 2)? as usize;
+    let section = |index: usize| table.checked_add(index.checked_mul(entry_size)?);
+    let dynamic = (0..count).find_map(|i| {
+        let header = section(i)?;
+        (read(header + 4, 4)? == u64::from(SHT_DYNAMIC)).then_some(header)
+    })?;
+    let strings = section(read(dynamic + sh_link, 4)? as usize)?;
+    let strings_offset = read(strings + sh_offset, word)? as usize;
+    let strings_size = read(strings + sh_size, word)? as usize;
+    let string_table = data.get(strings_offset..strings_offset.checked_add(strings_size)?)?;
+
+    let start = read(dynamic + sh_offset, word)? as usize;
+    let size = read(dynamic + sh_size, word)? as usize;
+    let mut needed = Vec::new();
+    for entry in (start..start.checked_add(size)?).step_by(2 * word) {
+        match read(entry, word)? {
+            DT_NULL => break,
+            DT_NEEDED => {
+                let name = string_table.get(read(entry + word, word)? as usize..)?;
+                let end = name.iter().position(|&b| b == 0)?;
+                needed.push(String::from_utf8_lossy(&name[..end]).to_string());
+            }
+            _ => {}
+        }
+    }
+    Some(Dynamic { needed, is_64bit })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_test_binary() {
+        let data = std::fs::read(std::env::current_exe().unwrap()).unwrap();
+        let dynamic = dynamic(&data).expect("the test binary is dynamically linked");
+        assert!(dynamic.needed.iter().any(|s| s.starts_with("libc.so")), "{:?}", dynamic.needed);
+        assert_eq!(dynamic.is_64bit, cfg!(target_pointer_width = "64"));
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert_eq!(dynamic(b"#!/bin/sh\n"), None);
+        assert_eq!(dynamic(b"\x7fELF not really"), None);
+        let data = std::fs::read(std::env::current_exe().unwrap()).unwrap();
+        assert_eq!(dynamic(&data[..64]), None);
+    }
+}
@@ -1,60 +1,249 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use anyhow::{Context as _, Result};
+use crate::bundle::plan::PlannedFile;
+use crate::bundle::staging::{EntryKind, Source, StagedTree};
+use crate::bundle::Context;
+use crate::config::{Config, InstallRoot};
+use crate::events;
+
+mod compress;
 mod desktop;
 
 mod deb_bundle;
+mod deb_control;
+mod deb_docs;
+mod elf;
+mod shlibs;
+mod pacman;
 mod rpm_bundle;
 mod tar_xz;
 mod tar_zst;
 mod appimage;
 
-pub use desktop::write_desktop_file;
+pub use desktop::desktop_file;
 
-pub use deb_bundle::bundle_deb;
-pub use rpm_bundle::bundle_rpm;
-pub use tar_xz::bundle_tar_xz;
-pub use tar_zst::bundle_tar_zst;
-pub use appimage::bundle_standalone;
+pub use deb_bundle::Deb;
+pub use rpm_bundle::Rpm;
+pub use tar_xz::TarXz;
+pub use tar_zst::TarZst;
+pub use appimage::AppImage;
 
-fn normalize_host_arch() -> &'static str {
-    match std::env::consts::ARCH {
-        "x86_64" => "x86_64",
-        "aarch64" => "aarch64",
-        "arm" | "armv7" | "armv7l" => "armhf",
-        "x86" | "i386" => "i386",
-        "riscv64" => "riscv64",
-        _ => "noarch",
-    }
-}
-pub fn filename_arch_name(eff: &str) -> &str {
-    match eff {
-        "x86_64" => "x86_64",
-        "aarch64" => "aarch64",
-        "armhf" => "armhfp",
-        "i386" => "i386",
-        "riscv64" => "riscv64",
-        _ => "noarch",
-    }
+/// File name of the bundled executable.
+pub fn bin_name(ctx: &Context) -> String {
+    ctx.binary
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| ctx.config.name.clone())
 }
 
+/// The `/usr` layout shared by every Linux format: binaries, desktop files, icons and resources.
+pub fn stage(ctx: &Context) -> Result<StagedTree> {
+    let config = ctx.config;
+    let mut tree = StagedTree::new();
+    tree.mtime = ctx.mtime;
+    for binary in &ctx.binaries {
+        let bin_name = binary.file_name();
+        tree.add_file(format!("usr/bin/{}", bin_name), Source::Path(binary.path.clone()), 0o755);
+        for alias in &binary.aliases {
+            tree.add_symlink(format!("usr/bin/{}", alias), &bin_name);
+        }
+    }
+    // The main launcher's entry is named after the package, further ones after their binary
+    for (i, (binary, name)) in ctx.launchers().enumerate() {
+        let bin_name = binary.file_name();
+        let file = match i {
+            0 => format!("usr/share/applications/{}.desktop", config.name),
+            _ => format!("usr/share/applications/{}-{}.desktop", config.name, bin_name),
+        };
+        tree.add_file(file, Source::Bytes(desktop_file(config, &name, &bin_name).into_bytes()), 0o644);
+    }
 
+    // Install the package's icons, re-encoded as PNG under their pixel size
+    let icons_dir = ctx.icons_dir.as_path();
+    if icons_dir.is_dir() {
+        for entry in fs::read_dir(icons_dir).context("failed to read icons dir")?.flatten() {
+            let path = entry.path();
+            if !path.is_file() {
+                continue;
+            }
+            match image::open(&path) {
+                Ok(img) => {
+                    let mut png = io::Cursor::new(Vec::new());
+                    img.write_to(&mut png, image::ImageFormat::Png)
+                        .with_context(|| format!("failed to encode {}", path.display()))?;
+                    tree.add_file(
+                        format!(
+                            "usr/share/icons/hicolor/{}x{}/apps/{}.png",
+                            img.width(),
+                            img.height(),
+                            config.name
+                        ),
+                        Source::Bytes(png.into_inner()),
+                        0o644,
+                    );
+                }
+                Err(_) => events::warning(format_args!("failed to read image {}", path.display())),
+            }
+        }
+    } else {
+        events::warning(format_args!(
+            "{} not found; no icons will be installed into package.",
+            icons_dir.display()
+        ));
+    }
 
-
-// Return the effective arch: if SLINT_BUNDLER_FORCE_ARCH is set, use that,
-// otherwise use the normalized host arch.
-pub fn effective_arch() -> String {
-    if let Ok(v) = std::env::var("SLINT_BUNDLER_FORCE_ARCH") {
-        return v;
+    for resource in &config.resources {
+        tree.add_file(
+            install_dir(config, resource.root).join(&resource.path),
+            Source::Path(resource.source.clone()),
+            resource.mode,
+        );
     }
-    normalize_host_arch().to_string()
+    Ok(tree)
 }
 
+/// Where the FHS puts a logical resource root.
+fn install_dir(config: &Config, root: InstallRoot) -> PathBuf {
+    match root {
+        InstallRoot::Bin => PathBuf::from("usr/bin"),
+        InstallRoot::Data => Path::new("usr/share").join(&config.name),
+        InstallRoot::Doc => Path::new("usr/share/doc").join(&config.name),
+        InstallRoot::Etc => PathBuf::from("etc"),
+    }
+}
 
-// Map effective arch to names used in filenames (keeps most mapping identical,
-// but maps "armhf" -> "armhfp" where a previous convention required it).
-
-
-
+/// Files and symlinks of `tree` for a dry-run plan; directories are implied.
+pub fn planned_files(tree: &StagedTree) -> Vec<PlannedFile> {
+    tree.entries()
+        .filter_map(|(path, entry)| {
+            let source = match &entry.kind {
+                EntryKind::Dir => return None,
+                EntryKind::File(Source::Path(source)) => source.display().to_string(),
+                EntryKind::File(Source::Bytes(bytes)) => format!("generated, {} bytes", bytes.len()),
+                EntryKind::Symlink(target) => format!("symlink to {}", target.display()),
+            };
+            Some(PlannedFile::new(path, source, Some(entry.mode)))
+        })
+        .collect()
+}
 
+/// Gzip encoder with a fixed header (no timestamp, unknown OS) so output is reproducible.
+pub fn gz_encoder<W: Write>(writer: W, level: u32) -> flate2::write::GzEncoder<W> {
+    flate2::GzBuilder::new()
+        .mtime(0)
+        .operating_system(255)
+        .write(writer, flate2::Compression::new(level))
+}
 
+/// Append every entry of `tree` to a tar archive, below `prefix`.
+pub fn append_tree<W: Write>(tar: &mut tar::Builder<W>, tree: &StagedTree, prefix: &Path) -> io::Result<()> {
+    for (path, entry) in tree.entries() {
+        let name = prefix.join(path);
+        let mut header = tar::Header::new_gnu();
+        header.set_mode(entry.mode);
+        header.set_uid(entry.owner.uid);
+        header.set_gid(entry.owner.gid);
+        header.set_username(&entry.owner.user)?;
+        header.set_groupname(&entry.owner.group)?;
+        header.set_mtime(tree.mtime);
+        match &entry.kind {
+            EntryKind::Dir => {
+                header.set_entry_type(tar::EntryType::Directory);
+                header.set_size(0);
+                tar.append_data(&mut header, &name, io::empty())?;
+            }
+            EntryKind::File(source) => {
+                let data = source.read()?;
+                header.set_entry_type(tar::EntryType::Regular);
+                header.set_size(data.len() as u64);
+                tar.append_data(&mut header, &name, data.as_slice())?;
+            }
+            EntryKind::Symlink(target) => {
+                header.set_entry_type(tar::EntryType::Symlink);
+                header.set_size(0);
+                tar.append_link(&mut header, &name, target)?;
+            }
+        }
+    }
+    Ok(())
+}
 
-// This will bundle as an AppImage
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::Duration;
+    use super::*;
+    use crate::bundle::bundler::Bundler;
+    use crate::bundle::tests::context;
+    use crate::config::Version;
+
+    pub(in crate::bundle::linux) fn config() -> Config {
+        Config {
+            name: "repro".to_string(),
+            version: Version::parse("1.0.0").unwrap(),
+            version_source: Default::default(),
+            identifier: "com.example.repro".to_string(),
+            display_name: "Repro".to_string(),
+            publisher: "Example".to_string(),
+            description: "Reproducibility test".to_string(),
+            homepage: None,
+            license: Some("MIT".to_string()),
+            license_file: None,
+            authors: vec!["Example <example@example.com>".to_string()],
+            categories: Vec::new(),
+            targets: Vec::new(),
+            bins: Vec::new(),
+            artifact_name: None,
+            channel: "stable".to_string(),
+            linux: Default::default(),
+            deb: Default::default(),
+            rpm: Default::default(),
+            tar_zst: Default::default(),
+            tar_xz: Default::default(),
+            windows: Default::default(),
+            android: Default::default(),
+            resources: Vec::new(),
+            hooks: Default::default(),
+        }
+    }
 
+    #[test]
+    fn bundles_are_byte_identical() {
+        let tmp = tempfile::tempdir().unwrap();
+        let binary = tmp.path().join("repro");
+        fs::write(&binary, b"\x7fELF not really").unwrap();
+        let config = config();
+        let bundlers: [&dyn Bundler; 5] = [&Deb, &Rpm, &TarZst, &TarXz, &AppImage];
+
+        let mut runs = Vec::new();
+        // Encoder threads must not change the output
+        for (run, threads) in [("a", 1), ("b", 4)] {
+            let mut ctx = context(&config, tmp.path());
+            ctx.binaries[0].aliases = vec!["repro-alias".to_string()];
+            ctx.bundle_dir = tmp.path().join(run);
+            ctx.threads = threads;
+            let mut outputs = Vec::new();
+            for bundler in bundlers {
+                for artifact in bundler.bundle(&ctx).unwrap() {
+                    // Every bundle must also pass `inspect`
+                    let report = crate::inspect::read(&artifact.path).unwrap();
+                    assert!(report.problems.is_empty(), "{:?}: {:?}", artifact.path, report.problems);
+                    let name = artifact.path.to_string_lossy();
+                    if name.ends_with(".tar.zst") || name.ends_with(".tar.xz") {
+                        assert_eq!(report.field("pkgname"), Some("repro"));
+                    }
+                    outputs.push((artifact.path.file_name().unwrap().to_owned(), fs::read(&artifact.path).unwrap()));
+                }
+            }
+            runs.push(outputs);
+            // Anything still taken from the clock would now differ
+            thread::sleep(Duration::from_millis(1100));
+        }
+        assert_eq!(runs[0].len(), runs[1].len());
+        for ((name, a), (_, b)) in runs[0].iter().zip(&runs[1]) {
+            assert!(a == b, "{:?} differs between runs", name);
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use std::fmt::Write as _;
+use anyhow::{Context as _, Result};
+use crate::bundle::linux::deb_control::maintainer;
+use crate::bundle::linux::stage;
+use crate::bundle::staging::{EntryKind, Source, StagedTree};
+use crate::bundle::Context;
+
+/// The shared `/usr` layout with the `.PKGINFO` pacman reads the package metadata from.
+/// It sorts before `usr`, so it is the first archive member as makepkg writes it.
+pub fn stage_package(ctx: &Context) -> Result<StagedTree> {
+    let mut tree = stage(ctx)?;
+    let pkginfo = pkginfo(ctx, &tree)?;
+    tree.add_file(".PKGINFO", Source::Bytes(pkginfo.into_bytes()), 0o644);
+    Ok(tree)
+}
+
+/// `.PKGINFO` of the package staged in `tree`, as `makepkg` writes it.
+fn pkginfo(ctx: &Context, tree: &StagedTree) -> Result<String> {
+    let config = ctx.config;
+    let mut size = 0;
+    for (path, entry) in tree.entries() {
+        if let EntryKind::File(source) = &entry.kind {
+            size += source.size().with_context(|| format!("failed to read {}", path.display()))?;
+        }
+    }
+    let mut info = String::new();
+    let _ = writeln!(info, "# Generated by slint-bundler");
+    let _ = writeln!(info, "pkgname = {}", config.name);
+    let _ = writeln!(info, "pkgbase = {}", config.name);
+    let _ = writeln!(info, "pkgver = {}-1", config.version.pacman()?);
+    let _ = writeln!(info, "pkgdesc = {}", config.description.trim().lines().next().unwrap_or_default());
+    if let Some(homepage) = &config.homepage {
+        let _ = writeln!(info, "url = {}", homepage);
+    }
+    let _ = writeln!(info, "builddate = {}", ctx.mtime);
+    let packager = maintainer(config).unwrap_or_else(|| config.publisher.clone());
+    let _ = writeln!(info, "packager = {}", packager);
+    let _ = writeln!(info, "size = {}", size);
+    let _ = writeln!(info, "arch = {}", ctx.arch.pacman());
+    if let Some(license) = &config.license {
+        let _ = writeln!(info, "license = {}", license);
+    }
+    Ok(info)
+}
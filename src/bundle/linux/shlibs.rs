@@ -0,0 +1,179 @@
+use std::collections::{BTreeSet, HashSet};
+use anyhow::{Context as _, Result};
+use crate::bundle::linux::elf;
+use crate::bundle::staging::{EntryKind, StagedTree};
+use crate::config::Config;
+use crate::events;
+
+/// Debian and Ubuntu packages providing the libraries Slint applications usually link.
+const DEBIAN: &[(&str, &str)] = &[
+    ("ld-linux-x86-64.so.2", "libc6"),
+    ("ld-linux-aarch64.so.1", "libc6"),
+    ("ld-linux-armhf.so.3", "libc6"),
+    ("libc.so.6", "libc6"),
+    ("libm.so.6", "libc6"),
+    ("libdl.so.2", "libc6"),
+    ("libpthread.so.0", "libc6"),
+    ("librt.so.1", "libc6"),
+    ("libgcc_s.so.1", "libgcc-s1"),
+    ("libstdc++.so.6", "libstdc++6"),
+    ("libz.so.1", "zlib1g"),
+    ("libssl.so.3", "libssl3"),
+    ("libcrypto.so.3", "libssl3"),
+    ("libfontconfig.so.1", "libfontconfig1"),
+    ("libfreetype.so.6", "libfreetype6"),
+    ("libxkbcommon.so.0", "libxkbcommon0"),
+    ("libxkbcommon-x11.so.0", "libxkbcommon-x11-0"),
+    ("libwayland-client.so.0", "libwayland-client0"),
+    ("libwayland-cursor.so.0", "libwayland-cursor0"),
+    ("libwayland-egl.so.1", "libwayland-egl1"),
+    ("libGL.so.1", "libgl1"),
+    ("libEGL.so.1", "libegl1"),
+    ("libGLX.so.0", "libglx0"),
+    ("libOpenGL.so.0", "libopengl0"),
+    ("libgbm.so.1", "libgbm1"),
+    ("libdrm.so.2", "libdrm2"),
+    ("libinput.so.10", "libinput10"),
+    ("libudev.so.1", "libudev1"),
+    ("libseat.so.1", "libseat1"),
+    ("libX11.so.6", "libx11-6"),
+    ("libX11-xcb.so.1", "libx11-xcb1"),
+    ("libxcb.so.1", "libxcb1"),
+    ("libXcursor.so.1", "libxcursor1"),
+    ("libXi.so.6", "libxi6"),
+    ("libXrandr.so.2", "libxrandr2"),
+    ("libdbus-1.so.3", "libdbus-1-3"),
+    ("libasound.so.2", "libasound2"),
+];
+
+/// Sonames the ELF files staged in `tree` need and it does not ship itself, each with
+/// whether a 64-bit file needs it.
+pub fn needed(tree: &StagedTree) -> Result<Vec<(String, bool)>> {
+    let shipped: HashSet<String> = tree
+        .entries()
+        .filter_map(|(path, _)| path.file_name())
+        .map(|name| name.to_string_lossy().to_string())
+        .collect();
+    let mut needed = BTreeSet::new();
+    for (path, entry) in tree.entries() {
+        let EntryKind::File(source) = &entry.kind else {
+            continue;
+        };
+        // Only executables and shared libraries can be dynamically linked
+        let library = path.file_name().is_some_and(|n| n.to_string_lossy().contains(".so"));
+        if entry.mode & 0o111 == 0 && !library {
+            continue;
+        }
+        let data = source.read().with_context(|| format!("failed to read {}", path.display()))?;
+        if let Some(dynamic) = elf::dynamic(&data) {
+            for soname in dynamic.needed.into_iter().filter(|s| !shipped.contains(s)) {
+                needed.insert((soname, dynamic.is_64bit));
+            }
+        }
+    }
+    Ok(needed.into_iter().collect())
+}
+
+/// Debian packages for `needed`, from `linux.shlibs.deb` or the built-in table.
+pub fn deb_depends(config: &Config, needed: &[(String, bool)]) -> Vec<String> {
+    let mut depends = BTreeSet::new();
+    for (soname, _) in needed {
+        let package = match config.linux.shlibs.deb.get(soname) {
+            Some(package) => package.as_str(),
+            None => match DEBIAN.iter().find(|(s, _)| s == soname) {
+                Some((_, package)) => package,
+                None => {
+                    events::warning(format_args!(
+                        "no Debian package known for {}, map it in linux.shlibs.deb",
+                        soname
+                    ));
+                    continue;
+                }
+            },
+        };
+        // An empty mapping drops the dependency
+        if !package.is_empty() {
+            depends.insert(package.to_string());
+        }
+    }
+    depends.into_iter().collect()
+}
+
+/// rpm `Requires` for `needed`: the soname capabilities every distribution's libraries
+/// provide, e.g. `libGL.so.1()(64bit)`, unless `linux.shlibs.rpm` maps them.
+pub fn rpm_requires(config: &Config, needed: &[(String, bool)]) -> Vec<String> {
+    let mut requires = BTreeSet::new();
+    for (soname, is_64bit) in needed {
+        let requirement = match config.linux.shlibs.rpm.get(soname) {
+            Some(package) => package.clone(),
+            None if *is_64bit => format!("{}()(64bit)", soname),
+            None => soname.clone(),
+        };
+        if !requirement.is_empty() {
+            requires.insert(requirement);
+        }
+    }
+    requires.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bundle::linux::tests::config;
+    use crate::bundle::staging::Source;
+
+    fn owned(sonames: &[(&str, bool)]) -> Vec<(String, bool)> {
+        sonames.iter().map(|(s, is_64bit)| (s.to_string(), *is_64bit)).collect()
+    }
+
+    #[test]
+    fn finds_needed_libraries() {
+        let exe = std::env::current_exe().unwrap();
+        let mut tree = StagedTree::new();
+        tree.add_file("usr/bin/app", Source::Path(exe.clone()), 0o755);
+        // Not executable and not a library, so never parsed
+        tree.add_file("usr/share/app/data.bin", Source::Path(exe.clone()), 0o644);
+        let found = needed(&tree).unwrap();
+        assert!(found.iter().any(|(s, _)| s.starts_with("libc.so")), "{:?}", found);
+
+        // Libraries the package ships itself are no dependency
+        let libc = found.iter().find(|(s, _)| s.starts_with("libc.so")).unwrap().0.clone();
+        tree.add_file(format!("usr/lib/app/{}", libc), Source::Bytes(Vec::new()), 0o644);
+        assert!(!needed(&tree).unwrap().iter().any(|(s, _)| *s == libc));
+    }
+
+    #[test]
+    fn deb_packages() {
+        let mut config = config();
+        config.linux.shlibs.deb.insert("libfoo.so.1".to_string(), "libfoo1".to_string());
+        config.linux.shlibs.deb.insert("libm.so.6".to_string(), String::new());
+        let sonames = [
+            ("libc.so.6", true),
+            ("libdl.so.2", true),
+            ("libm.so.6", true),
+            ("libGL.so.1", true),
+            ("libfoo.so.1", true),
+            ("libunknown.so.7", true),
+        ];
+        // libc and libdl share a package, libm is dropped and the unknown soname only warns
+        assert_eq!(deb_depends(&config, &owned(&sonames)), ["libc6", "libfoo1", "libgl1"]);
+    }
+
+    #[test]
+    fn rpm_requirements() {
+        let mut config = config();
+        config.linux.shlibs.rpm.insert("libfoo.so.1".to_string(), "foo-libs".to_string());
+        config.linux.shlibs.rpm.insert("libm.so.6".to_string(), String::new());
+        let sonames = [
+            ("libGL.so.1", true),
+            ("libc.so.6", false),
+            ("libm.so.6", true),
+            ("libfoo.so.1", true),
+            ("libunknown.so.7", true),
+        ];
+        assert_eq!(
+            rpm_requires(&config, &owned(&sonames)),
+            ["foo-libs", "libGL.so.1()(64bit)", "libc.so.6", "libunknown.so.7()(64bit)"]
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+use crate::error::{Error, Result};
+use crate::events::status;
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+pub const CHECKSUMS_FILE: &str = "SHA256SUMS";
+
+/// `manifest.json` next to the bundles of one target, read by the download page and updater.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    pub artifacts: Vec<ManifestEntry>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Relative to the manifest, with `/` separators, e.g. `deb/app_1.0.0_amd64.deb`.
+    pub path: String,
+    pub package: String,
+    /// Bundle name as used with `--bundles`.
+    pub format: String,
+    /// Target triple the binary was built for.
+    pub target: String,
+    pub version: String,
+    pub size: u64,
+    pub sha256: String,
+    /// Hash of the executable inside the bundle.
+    pub binary_sha256: String,
+}
+
+impl ManifestEntry {
+    /// Describe the artifact at `path`, which must lie below `root`.
+    pub fn new(
+        root: &Path,
+        path: &Path,
+        package: &str,
+        format: &str,
+        target: &str,
+        version: &str,
+        binary_sha256: &str,
+    ) -> anyhow::Result<ManifestEntry> {
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        Ok(ManifestEntry {
+            path: relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+            package: package.to_string(),
+            format: format.to_string(),
+            target: target.to_string(),
+            version: version.to_string(),
+            size: fs::metadata(path).with_context(|| format!("failed to read {}", path.display()))?.len(),
+            sha256: sha256::try_digest(path).with_context(|| format!("failed to hash {}", path.display()))?,
+            binary_sha256: binary_sha256.to_string(),
+        })
+    }
+}
+
+impl Manifest {
+    /// Write `manifest.json` and `SHA256SUMS` into `dir`.
+    pub fn write(&self, dir: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).context("failed to serialize manifest")?;
+        fs::write(dir.join(MANIFEST_FILE), json + "\n")?;
+        let sums: String = self
+            .artifacts
+            .iter()
+            .map(|a| format!("{}  {}\n", a.sha256, a.path))
+            .collect();
+        fs::write(dir.join(CHECKSUMS_FILE), sums)?;
+        Ok(())
+    }
+
+    /// Add the entries of the `manifest.json` already in `dir` that `self` does not replace,
+    /// so bundling one format after another keeps listing all of them. An entry is replaced by
+    /// a new one for the same path or the same package, format and target, so older versions
+    /// drop out. Entries whose file is gone are dropped too.
+    pub fn merge_existing(&mut self, dir: &Path) {
+        let Ok(existing) = Manifest::read(dir) else {
+            return;
+        };
+        for entry in existing.artifacts {
+            let replaced = self.artifacts.iter().any(|a| {
+                a.path == entry.path
+                    || (a.package == entry.package && a.format == entry.format && a.target == entry.target)
+            });
+            if !replaced && dir.join(&entry.path).is_file() {
+                self.artifacts.push(entry);
+            }
+        }
+        self.artifacts.sort_by(|a, b| a.path.cmp(&b.path));
+    }
+
+    pub fn read(dir: &Path) -> Result<Manifest> {
+        let path = dir.join(MANIFEST_FILE);
+        let json = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        Ok(serde_json::from_str(&json).with_context(|| format!("invalid {}", path.display()))?)
+    }
+}
+
+/// Re-check every artifact listed in `dir/manifest.json` against its size and hash.
+pub fn verify(dir: &Path) -> Result<()> {
+    let manifest = Manifest::read(dir)?;
+    let mut failed = 0;
+    for entry in &manifest.artifacts {
+        let path: PathBuf = dir.join(&entry.path);
+        let problem = match fs::metadata(&path) {
+            Err(_) => Some("missing".to_string()),
+            Ok(meta) if meta.len() != entry.size => {
+                Some(format!("size is {} bytes, expected {}", meta.len(), entry.size))
+            }
+            Ok(_) => match sha256::try_digest(path.as_path()) {
+                Ok(hash) if hash == entry.sha256 => None,
+                Ok(_) => Some("sha256 mismatch".to_string()),
+                Err(e) => Some(format!("failed to hash: {}", e)),
+            },
+        };
+        match problem {
+            None => status!("OK      {}", entry.path),
+            Some(problem) => {
+                failed += 1;
+                status!("FAILED  {} ({})", entry.path, problem);
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(Error::Verify(format!(
+            "{} of {} artifacts in {} failed verification",
+            failed,
+            manifest.artifacts.len(),
+            dir.display()
+        )));
+    }
+    status!("{} artifacts verified", manifest.artifacts.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bundle(root: &Path, path: &str, format: &str, data: &[u8]) -> ManifestEntry {
+        bundle_version(root, path, format, "1.0.0", data)
+    }
+
+    fn bundle_version(root: &Path, path: &str, format: &str, version: &str, data: &[u8]) -> ManifestEntry {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, data).unwrap();
+        ManifestEntry::new(root, &path, "app", format, "x86_64-unknown-linux-gnu", version, "0000").unwrap()
+    }
+
+    fn write(root: &Path, artifacts: Vec<ManifestEntry>) {
+        let mut manifest = Manifest { artifacts };
+        manifest.merge_existing(root);
+        manifest.write(root).unwrap();
+    }
+
+    #[test]
+    fn later_builds_add_to_the_manifest() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        write(root, vec![bundle(root, "deb/app_1.0.0_amd64.deb", "deb", b"deb")]);
+        write(root, vec![bundle(root, "rpm/app-1.0.0-1.x86_64.rpm", "rpm", b"rpm")]);
+        let paths = |m: Manifest| m.artifacts.into_iter().map(|a| a.path).collect::<Vec<_>>();
+        assert_eq!(
+            paths(Manifest::read(root).unwrap()),
+            ["deb/app_1.0.0_amd64.deb", "rpm/app-1.0.0-1.x86_64.rpm"]
+        );
+        assert_eq!(fs::read_to_string(root.join(CHECKSUMS_FILE)).unwrap().lines().count(), 2);
+        verify(root).unwrap();
+
+        // A rebuilt bundle replaces its entry, a deleted one is dropped
+        let deb = bundle(root, "deb/app_1.0.0_amd64.deb", "deb", b"rebuilt deb");
+        let sha256 = deb.sha256.clone();
+        fs::remove_file(root.join("rpm/app-1.0.0-1.x86_64.rpm")).unwrap();
+        write(root, vec![deb]);
+        let manifest = Manifest::read(root).unwrap();
+        assert_eq!(manifest.artifacts.len(), 1);
+        assert_eq!(manifest.artifacts[0].sha256, sha256);
+        verify(root).unwrap();
+
+        fs::write(root.join("deb/app_1.0.0_amd64.deb"), b"tampered").unwrap();
+        assert!(matches!(verify(root), Err(Error::Verify(_))));
+    }
+    #[test]
+    fn new_versions_replace_old_ones() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        write(root, vec![
+            bundle_version(root, "deb/app_1.0.0_amd64.deb", "deb", "1.0.0", b"deb"),
+            bundle_version(root, "rpm/app-1.0.0-1.x86_64.rpm", "rpm", "1.0.0", b"rpm"),
+        ]);
+        // The old files stay on disk, but only the new deb and the untouched rpm are listed
+        write(root, vec![bundle_version(root, "deb/app_1.1.0_amd64.deb", "deb", "1.1.0", b"deb 1.1")]);
+        let manifest = Manifest::read(root).unwrap();
+        let entries: Vec<_> = manifest.artifacts.iter().map(|a| (a.path.as_str(), a.version.as_str())).collect();
+        assert_eq!(entries, [("deb/app_1.1.0_amd64.deb", "1.1.0"), ("rpm/app-1.0.0-1.x86_64.rpm", "1.0.0")]);
+        assert_eq!(fs::read_to_string(root.join(CHECKSUMS_FILE)).unwrap().lines().count(), 2);
+    }
+}
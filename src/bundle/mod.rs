@@ -1,69 +1,860 @@
+use std::env;
+use std::io;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+use anyhow::{bail, Context as _};
+use crate::config::{self, BinConfig, Config};
+use crate::error::{Error, Result};
+use crate::events::{self, status, Event};
+use crate::project::{self, Package, Project, Target};
+mod arch;
+mod bundler;
+mod cache;
+mod hooks;
+mod manifest;
+mod plan;
+mod summary;
+pub use arch::Arch;
+use bundler::{Artifact, Bundler};
+use cache::Cache;
+use manifest::{Manifest, ManifestEntry, CHECKSUMS_FILE};
+use plan::{print_plan, print_tools};
+use summary::{print_summary, Outcome};
 #[cfg(target_os = "linux")]
 mod linux;
 #[cfg(target_os = "linux")]
-use crate::bundle::linux::{bundle_deb, bundle_rpm, bundle_tar_zst, bundle_tar_xz, bundle_standalone};
+mod staging;
 
-
-// Windows-only imports
+// Windows-only modules
 #[cfg(target_os = "windows")]
 mod windows;
-#[cfg(target_os = "windows")]
-use crate::bundle::windows::{bundle_msi, bundle_nsis, bundle_msix};
 
 
+/// Options of the `build` command.
+#[derive(Clone, Debug)]
+pub struct BuildOptions {
+    pub bundles: Option<Vec<String>>,
+    pub profile: String,
+    /// `-p/--package`, may be repeated. Empty means the package in the current directory.
+    pub packages: Vec<String>,
+    /// `--workspace`: bundle every member with a binary target.
+    pub workspace: bool,
+    /// `--target`/`--targets` triples to cross-compile for. Empty falls back to the
+    /// `targets` matrix in the configuration, then to the host.
+    pub targets: Vec<String>,
+    /// `--dry-run`: print the plan instead of building.
+    pub dry_run: bool,
+    /// `--reproducible`, or running under CI: use a fixed timestamp for every packaged file.
+    pub reproducible: bool,
+    /// `--force`: ignore the bundle cache.
+    pub force: bool,
+    /// `--jobs`: how many bundles are created at the same time.
+    pub jobs: usize,
+    /// `--out-dir`: root for bundles instead of `target/<profile>/bundle`.
+    pub out_dir: Option<PathBuf>,
+    /// `--channel`: overrides the configured release channel.
+    pub channel: Option<String>,
+}
+
+/// Everything a bundler needs to package the binaries of one package.
+#[derive(Clone)]
+pub struct Context<'a> {
+    pub config: &'a Config,
+    /// The main executable: the first launcher, otherwise the first packaged binary.
+    pub binary: PathBuf,
+    /// Every executable to package, `binary` included, in configuration order.
+    pub binaries: Vec<Binary>,
+    /// Root output folder; each format writes into its own subfolder.
+    pub bundle_dir: PathBuf,
+    /// The package's `icons` folder, next to its `Cargo.toml`.
+    pub icons_dir: PathBuf,
+    /// Worker threads each compressor may use, so parallel bundles share the CPUs.
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    pub threads: u32,
+    /// Target triple the binaries were built for.
+    pub target: String,
+    /// Abbreviated commit hash, `unknown` outside git.
+    pub git_sha: String,
+    /// Architecture of `binary`, used for package metadata and file names.
+    pub arch: Arch,
+    /// Modification time recorded for every packaged file, in seconds since the epoch.
+    pub mtime: u64,
+}
 
+/// A compiled executable that goes into the bundle.
+#[derive(Clone, Debug)]
+pub struct Binary {
+    pub path: PathBuf,
+    /// Gets a desktop entry or Start menu shortcut.
+    pub launcher: bool,
+    /// Further command names, e.g. symlinks in `/usr/bin`.
+    pub aliases: Vec<String>,
+}
 
-pub fn handle_build(bundles: Option<Vec<String>>) {
-    // 1. Build the project in release mode
-    let status = Command::new("cargo")
-        .arg("build")
-        .arg("--release")
-        .arg("--bin")
-        .status()
-        .expect("Failed to run cargo build");
-    if !status.success() {
-        eprintln!("Build failed");
-        std::process::exit(1);
+impl Binary {
+    /// File name, e.g. `app` or `app.exe`.
+    pub fn file_name(&self) -> String {
+        self.path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+    }
+}
+
+impl Context<'_> {
+    /// Path of an artifact in the format's `dir`, named by the `artifact-name` template or
+    /// else the format's conventional `default` one. `version` and `arch` are in the
+    /// format's own notation and `extension` includes the leading dot.
+    pub fn artifact_path(&self, dir: &str, default: &str, version: &str, arch: &str, extension: &str) -> PathBuf {
+        let config = self.config;
+        let name = config
+            .artifact_name
+            .as_deref()
+            .unwrap_or(default)
+            .replace("{name}", &config.name)
+            .replace("{version}", version)
+            .replace("{arch}", arch)
+            .replace("{target}", &self.target)
+            .replace("{channel}", &config.channel)
+            .replace("{git_sha}", &self.git_sha);
+        self.bundle_dir.join(dir).join(format!("{}{}", name, extension))
+    }
+
+    /// Launcher binaries with the name their menu entry shows: the main launcher uses
+    /// the display name, further ones a name derived from the binary.
+    pub fn launchers(&self) -> impl Iterator<Item = (&Binary, String)> {
+        self.binaries.iter().filter(|b| b.launcher).enumerate().map(|(i, binary)| {
+            let name = match i {
+                0 => self.config.display_name.clone(),
+                _ => config::prettify_name(&binary.path.file_stem().unwrap_or_default().to_string_lossy()),
+            };
+            (binary, name)
+        })
+    }
+}
+
+/// Settings shared by every bundle of one `build` run.
+struct Layout<'a> {
+    project: &'a Project,
+    profile: &'a str,
+    /// Fixed timestamp for packaged files; `None` uses the current time.
+    source_date: Option<u64>,
+    cache: Cache,
+    /// Bundle even when the cache has an entry for the same inputs.
+    force: bool,
+    /// Bundlers running at the same time.
+    jobs: usize,
+    /// `--out-dir`, made absolute.
+    out_dir: Option<PathBuf>,
+    /// More than one target is bundled, so `out_dir` gets a folder per target.
+    multi_target: bool,
+    git_sha: String,
+}
+
+/// A package selected for bundling together with its configuration.
+struct Job<'a> {
+    package: &'a Package,
+    /// `[[bin]]` targets to package with their settings.
+    bins: Vec<(&'a Target, BinConfig)>,
+    config: Config,
+    /// Triples to bundle for; `None` is the host.
+    targets: Vec<Option<String>>,
+}
+
+/// Check the artifacts listed in `dir/manifest.json`; without `dir`, in the bundle folder
+/// `build` writes for `profile` and `target`, below cargo's target directory.
+pub fn handle_verify(dir: Option<PathBuf>, profile: &str, target: Option<&str>) -> Result<()> {
+    let dir = match dir {
+        Some(dir) => dir,
+        None => {
+            let project = Project::load().map_err(Error::Config)?;
+            project.artifact_dir(profile, target).join("bundle")
+        }
+    };
+    manifest::verify(&dir)
+}
+
+pub fn handle_build(options: BuildOptions) -> Result<()> {
+    let project = Project::load().map_err(Error::Config)?;
+    let packages = project
+        .select(&options.packages, options.workspace)
+        .map_err(|e| Error::Usage(format!("{:#}", e)))?;
+    let mut jobs: Vec<Job> = Vec::new();
+    for package in packages {
+        let mut config = Config::for_package(&project, package).map_err(Error::Config)?;
+        if let Some(channel) = &options.channel {
+            config.channel = channel.clone();
+        }
+        let targets = if !options.targets.is_empty() {
+            options.targets.iter().cloned().map(Some).collect()
+        } else if !config.targets.is_empty() {
+            config.targets.iter().cloned().map(Some).collect()
+        } else {
+            vec![None]
+        };
+        jobs.push(Job {
+            package,
+            bins: package_bins(package, &config).map_err(Error::Config)?,
+            config,
+            targets,
+        });
+    }
+
+    // Determine bundles to create
+    let names = options
+        .bundles
+        .unwrap_or_else(|| bundler::defaults().iter().map(|s| s.to_string()).collect());
+    let bundles = names
+        .iter()
+        .map(|name| {
+            bundler::find(name).ok_or_else(|| {
+                Error::Usage(format!(
+                    "unknown bundle type `{}`, expected one of: {}",
+                    name,
+                    bundler::registry().iter().map(|b| b.name()).collect::<Vec<_>>().join(", ")
+                ))
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    // Every triple any package asks for, in the order they were given
+    let mut matrix: Vec<Option<String>> = Vec::new();
+    for target in jobs.iter().flat_map(|j| &j.targets) {
+        if !matrix.contains(target) {
+            matrix.push(target.clone());
+        }
+    }
+
+    // Reject targets without a package architecture before building anything
+    for target in &matrix {
+        target_arch(&target_triple(target.as_deref()))?;
     }
 
-    // 2. Determine bundles to create
-    let os = std::env::consts::OS;
-    let all_bundles = match os {
-        "windows" => vec!["msi", "nsis", "msix"],
-        "linux" => vec!["deb", "rpm", "tar.zst", "tar.xz", "standalone"],
-        _ => vec!["standalone"],
+    let layout = Layout {
+        project: &project,
+        profile: &options.profile,
+        source_date: source_date(options.reproducible)?,
+        cache: Cache::new(&project.target_directory),
+        force: options.force,
+        jobs: options.jobs,
+        out_dir: options.out_dir.map(|dir| env::current_dir().map(|cwd| cwd.join(dir))).transpose()?,
+        multi_target: matrix.len() > 1,
+        git_sha: git_sha(&project.workspace_root),
     };
-    let bundles = bundles.unwrap_or_else(|| all_bundles.iter().map(|s| s.to_string()).collect());
 
-    // 3. Bundle for each requested type. If cross_arch flag is set, attempt to
-    // create bundles for several non-host architectures by setting the
-    // SLINT_BUNDLER_FORCE_ARCH environment variable per attempt.
-    // let archs_to_try = vec!["i386", "x86_64", "aarch64", "armhf", "riscv64"];
-    
+    if options.dry_run {
+        return print_dry_run(&layout, &matrix, &jobs, &bundles);
+    }
+
+    events::emit(&Event::BuildStarted {
+        packages: jobs.iter().map(|j| j.package.name.as_str()).collect(),
+        targets: matrix.iter().map(|t| t.as_deref().unwrap_or("host")).collect(),
+        profile: &options.profile,
+    });
+
+    let mut outcomes = Vec::new();
+    let mut failures = Vec::new();
+    for job in &jobs {
+        if let Some(command) = &job.config.hooks.pre_build {
+            let mut env = hook_env(&layout, job);
+            env.push(("SLINT_BUNDLER_TARGETS", target_labels(&job.targets)));
+            if let Err(e) = hooks::run("pre-build", command, job.package.manifest_dir(), &env) {
+                failures.push(e);
+                break;
+            }
+        }
+    }
+    for target in &matrix {
+        // A failed hook stops the remaining targets
+        if failures.iter().any(|e| matches!(e, Error::Hook { .. })) {
+            break;
+        }
+        let members: Vec<&Job> = jobs.iter().filter(|j| j.targets.contains(target)).collect();
+        let label = target.clone().unwrap_or_else(|| "host".to_string());
+        if matrix.len() > 1 {
+            status!("Building for {}...", label);
+        }
+        let built = build(&options.profile, target.as_deref(), &members);
+        events::emit(&Event::CargoFinished {
+            target: &label,
+            success: built.is_ok(),
+        });
+        match built {
+            Ok(()) => bundle_target(
+                &layout,
+                target.as_deref(),
+                &members,
+                &bundles,
+                &mut outcomes,
+                &mut failures,
+            ),
+            Err(e) => {
+                for job in &members {
+                    for bundle in &bundles {
+                        outcomes.push(Outcome {
+                            package: job.package.name.clone(),
+                            target: label.clone(),
+                            bundle: bundle.name().to_string(),
+                            result: Err(e.to_string()),
+                        });
+                    }
+                }
+                failures.push(e);
+            }
+        }
+    }
+    for job in &jobs {
+        if !failures.is_empty() {
+            break;
+        }
+        if let Some(command) = &job.config.hooks.post_build {
+            let artifacts: Vec<String> = outcomes
+                .iter()
+                .filter(|o| o.package == job.package.name)
+                .filter_map(|o| o.result.as_ref().ok())
+                .map(|path| path.display().to_string())
+                .collect();
+            let mut env = hook_env(&layout, job);
+            env.push(("SLINT_BUNDLER_TARGETS", target_labels(&job.targets)));
+            env.push(("SLINT_BUNDLER_ARTIFACTS", artifacts.join("\n")));
+            if let Err(e) = hooks::run("post-build", command, job.package.manifest_dir(), &env) {
+                failures.push(e);
+                break;
+            }
+        }
+    }
+
+    print_summary(&outcomes);
+    events::emit(&Event::BuildFinished {
+        success: failures.is_empty(),
+        artifacts: outcomes.iter().filter(|o| o.result.is_ok()).count(),
+        failures: failures.len(),
+    });
+    match failures.len() {
+        0 => Ok(()),
+        1 => Err(failures.remove(0)),
+        _ => Err(Error::Bundles(failures)),
+    }
+}
+
+/// Variables every hook of `job` sees.
+fn hook_env(layout: &Layout, job: &Job) -> Vec<(&'static str, String)> {
+    vec![
+        ("SLINT_BUNDLER_PACKAGE", job.package.name.clone()),
+        ("SLINT_BUNDLER_VERSION", job.config.version.to_string()),
+        ("SLINT_BUNDLER_PROFILE", layout.profile.to_string()),
+    ]
+}
+
+/// `targets` as given to hooks: space separated triples, `host` for the host.
+fn target_labels(targets: &[Option<String>]) -> String {
+    targets
+        .iter()
+        .map(|t| t.as_deref().unwrap_or("host"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The `[[bin]]` targets listed in the configuration, or the main one as the launcher.
+fn package_bins<'a>(package: &'a Package, config: &Config) -> anyhow::Result<Vec<(&'a Target, BinConfig)>> {
+    if config.bins.is_empty() {
+        let bin = package.main_bin()?;
+        let main = BinConfig {
+            name: bin.name.clone(),
+            launcher: true,
+            aliases: Vec::new(),
+        };
+        return Ok(vec![(bin, main)]);
+    }
+    let mut names: Vec<&str> = Vec::new();
+    let mut bins = Vec::new();
+    for bin in &config.bins {
+        let target = package
+            .bins()
+            .find(|t| t.name == bin.name)
+            .with_context(|| format!("package `{}` has no binary target `{}`", package.name, bin.name))?;
+        for name in std::iter::once(&bin.name).chain(&bin.aliases) {
+            if names.contains(&name.as_str()) {
+                bail!("`{}` is listed more than once in bins of package `{}`", name, package.name);
+            }
+            names.push(name);
+        }
+        bins.push((target, bin.clone()));
+    }
+    Ok(bins)
+}
+
+/// Print what `build` would do for every target, package and bundle without running it.
+fn print_dry_run(
+    layout: &Layout,
+    matrix: &[Option<String>],
+    jobs: &[Job],
+    bundles: &[&dyn Bundler],
+) -> Result<()> {
+    let mut tools = vec!["cargo"];
+    for job in jobs {
+        if let Some(command) = &job.config.hooks.pre_build {
+            status!("{} pre-build hook: {}", job.package.name, command);
+        }
+    }
+    for target in matrix {
+        let members: Vec<&Job> = jobs.iter().filter(|j| j.targets.contains(target)).collect();
+        let label = target.as_deref().unwrap_or("host");
+        let cargo = cargo_command(layout.profile, target.as_deref(), &members);
+        status!("Target {}:", label);
+        status!(
+            "  {} {}",
+            cargo.get_program().to_string_lossy(),
+            cargo.get_args().map(|a| a.to_string_lossy()).collect::<Vec<_>>().join(" ")
+        );
+        for job in &members {
+            let ctx = layout.context(target.as_deref(), job)?;
+            for bundler in bundles {
+                let plan = bundler.plan(&ctx).map_err(|e| Error::Bundle {
+                    bundle: bundler.name().to_string(),
+                    error: e,
+                })?;
+                print_plan(&format!("  {} {}:", job.package.name, bundler.name()), &plan);
+                let hooks = &job.config.hooks;
+                for (hook, format_hook) in [("pre-bundle", &hooks.pre_bundle), ("post-bundle", &hooks.post_bundle)] {
+                    if let Some(command) = format_hook.as_ref().and_then(|h| h.command(bundler.name())) {
+                        status!("    {} hook: {}", hook, command);
+                    }
+                }
+                for tool in plan.tools {
+                    if !tools.contains(&tool) {
+                        tools.push(tool);
+                    }
+                }
+            }
+        }
+    }
+    for job in jobs {
+        if let Some(command) = &job.config.hooks.post_build {
+            status!("{} post-build hook: {}", job.package.name, command);
+        }
+    }
+    status!("Tools:");
+    print_tools(&tools);
+    Ok(())
+}
+
+/// The `cargo build` invocation for all `members` of one target.
+fn cargo_command(profile: &str, target: Option<&str>, members: &[&Job]) -> Command {
+    let mut cargo = Command::new("cargo");
+    cargo.arg("build").arg("--profile").arg(profile);
+    if let Some(target) = target {
+        cargo.arg("--target").arg(target);
+    }
+    for job in members {
+        cargo.arg("--package").arg(&job.package.name);
+        for (bin, _) in &job.bins {
+            cargo.arg("--bin").arg(&bin.name);
+        }
+    }
+    cargo
+}
 
-    // produce host bundles (or only bundles if cross_arch was false)
-    
-    #[cfg(target_os = "linux")]
-    for bundle in bundles {
-        match bundle.as_str() {
-            "deb" => bundle_deb(),
-            "rpm" => bundle_rpm(),
-            "tar.zst" => bundle_tar_zst(),
-            "tar.xz" => bundle_tar_xz(),
-            "standalone" => bundle_standalone(),
-            _ => eprintln!("Unknown bundle type: {}", bundle),
+/// Run one `cargo build` for all `members`.
+fn build(profile: &str, target: Option<&str>, members: &[&Job]) -> Result<()> {
+    let mut cargo = cargo_command(profile, target, members);
+    let status = cargo.stdout(events::child_stdout()).status().map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => Error::missing_tool("cargo"),
+        _ => Error::Build(format!("failed to run cargo build: {}", e)),
+    })?;
+    if !status.success() {
+        let target = target.map(|t| format!(" for {}", t)).unwrap_or_default();
+        return Err(Error::Build(format!("cargo build failed{} ({})", target, status)));
+    }
+    Ok(())
+}
+
+/// Produce every bundle of `members` for one target, recording an outcome for each,
+/// then write `manifest.json` and `SHA256SUMS` for the artifacts.
+fn bundle_target(
+    layout: &Layout,
+    target: Option<&str>,
+    members: &[&Job],
+    bundles: &[&dyn Bundler],
+    outcomes: &mut Vec<Outcome>,
+    failures: &mut Vec<Error>,
+) {
+    let root = layout.bundle_root(target);
+    let label = target.unwrap_or("host");
+    let mut contexts: Vec<(&Job, Context, String)> = Vec::new();
+    for job in members {
+        match layout.context(target, job) {
+            Ok(ctx) => {
+                let binary_sha256 = sha256::try_digest(ctx.binary.as_path()).unwrap_or_default();
+                contexts.push((*job, ctx, binary_sha256));
+            }
+            Err(e) => {
+                // None of the package's bundles can run, so each gets a failed row in the summary
+                for bundler in bundles {
+                    outcomes.push(Outcome {
+                        package: job.package.name.clone(),
+                        target: label.to_string(),
+                        bundle: bundler.name().to_string(),
+                        result: Err(e.to_string()),
+                    });
+                }
+                failures.push(e);
+            }
         }
     }
+    let tasks: Vec<(usize, &dyn Bundler)> = (0..contexts.len())
+        .flat_map(|i| bundles.iter().map(move |bundler| (i, *bundler)))
+        .collect();
+    // Fewer tasks than `--jobs` leave more CPUs to each compressor
+    let threads = encoder_threads(layout.jobs.min(tasks.len()));
+    for (_, ctx, _) in &mut contexts {
+        ctx.threads = threads;
+    }
+
+    // Bundlers only share read-only inputs, so up to `--jobs` of them run at once
+    let results = run_parallel(layout.jobs, &tasks, |&(i, bundler)| {
+        let (job, ctx, binary_sha256) = &contexts[i];
+        if members.len() > 1 {
+            status!("Bundling {} {}...", job.package.name, bundler.name());
+        }
+        events::emit(&Event::BundleStarted {
+            package: &job.package.name,
+            format: bundler.name(),
+            target: label,
+        });
+        run_cached(layout, job, ctx, bundler, label, binary_sha256).and_then(|artifacts| {
+            artifacts
+                .into_iter()
+                .map(|artifact| {
+                    ManifestEntry::new(
+                        &root,
+                        &artifact.path,
+                        &job.package.name,
+                        bundler.name(),
+                        &ctx.target,
+                        &ctx.config.version.to_string(),
+                        binary_sha256,
+                    )
+                    .map(|entry| (artifact, entry))
+                    .map_err(|e| Error::Bundle {
+                        bundle: bundler.name().to_string(),
+                        error: e,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()
+        })
+    });
+
+    let mut manifest = Manifest::default();
+    for (&(i, bundler), recorded) in tasks.iter().zip(results) {
+        let (job, ctx, _) = &contexts[i];
+        let outcome = |result| Outcome {
+            package: job.package.name.clone(),
+            target: label.to_string(),
+            bundle: bundler.name().to_string(),
+            result,
+        };
+        match recorded {
+            Ok(recorded) => {
+                for (artifact, entry) in recorded {
+                    events::emit(&Event::ArtifactCreated {
+                        package: &entry.package,
+                        format: &entry.format,
+                        arch: ctx.arch.name(),
+                        target: label,
+                        path: &artifact.path,
+                        size: entry.size,
+                        sha256: entry.sha256.clone(),
+                    });
+                    outcomes.push(outcome(Ok(artifact.path)));
+                    manifest.artifacts.push(entry);
+                }
+            }
+            Err(e) => {
+                outcomes.push(outcome(Err(e.to_string())));
+                failures.push(e);
+            }
+        }
+    }
+
+    if manifest.artifacts.is_empty() {
+        return;
+    }
+    manifest.merge_existing(&root);
+    match manifest.write(&root) {
+        Ok(()) => status!("Wrote {}", root.join(CHECKSUMS_FILE).display()),
+        Err(e) => failures.push(e),
+    }
+}
+
+impl Layout<'_> {
+    /// `target/<profile>/bundle` or `--out-dir`, holding the bundles, checksums and manifest
+    /// of one target.
+    fn bundle_root(&self, target: Option<&str>) -> PathBuf {
+        match &self.out_dir {
+            Some(dir) if self.multi_target => dir.join(target.unwrap_or("host")),
+            Some(dir) => dir.clone(),
+            None => self.project.artifact_dir(self.profile, target).join("bundle"),
+        }
+    }
+
+    /// Scratch folder a pre-bundle hook can stage extra files in.
+    fn staging_dir(&self, package: &str, target: &str, format: &str) -> PathBuf {
+        self.project
+            .target_directory
+            .join("slint-bundler")
+            .join("staging")
+            .join(package)
+            .join(target)
+            .join(format)
+    }
+
+    /// The bundler context of `job` for one target.
+    fn context<'j>(&self, target: Option<&str>, job: &'j Job) -> Result<Context<'j>> {
+        let triple = target_triple(target);
+        let arch = target_arch(&triple)?;
+        // Workspaces get one folder per package so members with equal bundle
+        // names do not overwrite each other.
+        let bundle_root = self.bundle_root(target);
+        let binaries: Vec<Binary> = job
+            .bins
+            .iter()
+            .map(|(bin, settings)| Binary {
+                path: self.project.bin_path(self.profile, target, bin),
+                launcher: settings.launcher,
+                aliases: settings.aliases.clone(),
+            })
+            .collect();
+        let main = binaries.iter().find(|b| b.launcher).unwrap_or(&binaries[0]);
+        Ok(Context {
+            config: &job.config,
+            binary: main.path.clone(),
+            binaries,
+            bundle_dir: if self.project.is_multi_package() {
+                bundle_root.join(&job.package.name)
+            } else {
+                bundle_root
+            },
+            icons_dir: job.package.manifest_dir().join("icons"),
+            threads: encoder_threads(self.jobs),
+            target: triple,
+            git_sha: self.git_sha.clone(),
+            arch,
+            mtime: self.source_date.unwrap_or_else(|| {
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0)
+            }),
+        })
+    }
+}
+
+/// Threads for each compressor when `parallel` bundles are created at once, so
+/// together they use about one thread per CPU.
+fn encoder_threads(parallel: usize) -> u32 {
+    let cpus = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    (cpus / parallel.max(1)).max(1) as u32
+}
+
+/// `target`, or the host triple rustc reports; `host` if it cannot tell.
+fn target_triple(target: Option<&str>) -> String {
+    target
+        .map(str::to_string)
+        .or_else(project::host_triple)
+        .unwrap_or_else(|| "host".to_string())
+}
+
+/// Package architecture of `triple`; targets the formats have no name for are a usage error.
+fn target_arch(triple: &str) -> Result<Arch> {
+    let arch = if triple == "host" { Arch::host() } else { Arch::from_triple(triple) };
+    arch.map_err(|e| Error::Usage(format!("{:#}", e)))
+}
 
-    #[cfg(target_os = "windows")]
-    for bundle in bundles {
-        match bundle.as_str() {
-            "msi" => bundle_msi(),
-            "nsis" => bundle_nsis(),
-            "msix" => bundle_msix(),
-            _ => eprintln!("Unknown bundle type: {}", bundle),
+/// Fixed timestamp for packaged files: `SOURCE_DATE_EPOCH` when set, otherwise the last
+/// commit (or the epoch outside git) in reproducible mode. `None` means the current time.
+fn source_date(reproducible: bool) -> Result<Option<u64>> {
+    if let Some(value) = env::var_os("SOURCE_DATE_EPOCH") {
+        let value = value.to_string_lossy();
+        return value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| Error::Usage(format!("SOURCE_DATE_EPOCH must be seconds since the epoch, got `{}`", value)));
+    }
+    if reproducible {
+        let commit_time = Command::new("git")
+            .args(["log", "-1", "--format=%ct"])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| String::from_utf8_lossy(&output.stdout).trim().parse().ok());
+        return Ok(Some(commit_time.unwrap_or(0)));
+    }
+    Ok(None)
+}
+
+/// Abbreviated hash of the checked out commit, for `{git_sha}` in artifact names.
+fn git_sha(dir: &Path) -> String {
+    Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .current_dir(dir)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Apply `f` to `items` on up to `jobs` threads, returning the results in input order.
+fn run_parallel<T: Sync, R: Send>(jobs: usize, items: &[T], f: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<R>>> = Mutex::new(items.iter().map(|_| None).collect());
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, items.len().max(1)) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(i) else { break };
+                let result = f(item);
+                results.lock().unwrap()[i] = Some(result);
+            });
         }
+    });
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|r| r.expect("every item is processed"))
+        .collect()
+}
+
+/// Run `bundler` between its pre- and post-bundle hooks, unless the cache holds its
+/// artifacts for identical inputs. The pre-bundle hook runs either way, since the files it
+/// stages are part of those inputs.
+fn run_cached(
+    layout: &Layout,
+    job: &Job,
+    ctx: &Context,
+    bundler: &dyn Bundler,
+    target: &str,
+    binary_sha256: &str,
+) -> Result<Vec<Artifact>> {
+    let format = bundler.name();
+    let package = job.package.name.as_str();
+    let hooks = &ctx.config.hooks;
+    let staging = layout.staging_dir(package, target, format);
+    let mut env = hook_env(layout, job);
+    env.extend([
+        ("SLINT_BUNDLER_TARGET", target.to_string()),
+        ("SLINT_BUNDLER_ARCH", ctx.arch.name().to_string()),
+        ("SLINT_BUNDLER_FORMAT", format.to_string()),
+        ("SLINT_BUNDLER_BINARY", ctx.binary.display().to_string()),
+        ("SLINT_BUNDLER_BUNDLE_DIR", ctx.bundle_dir.display().to_string()),
+        ("SLINT_BUNDLER_STAGING_DIR", staging.display().to_string()),
+    ]);
+
+    // Files the pre-bundle hook stages are packaged like resources
+    let staged_config;
+    let staged_ctx;
+    let ctx = match hooks.pre_bundle.as_ref().and_then(|h| h.command(format)) {
+        Some(command) => {
+            let bundle_error = |error: anyhow::Error| Error::Bundle {
+                bundle: format.to_string(),
+                error,
+            };
+            if staging.exists() {
+                std::fs::remove_dir_all(&staging)
+                    .with_context(|| format!("failed to clear {}", staging.display()))
+                    .map_err(bundle_error)?;
+            }
+            std::fs::create_dir_all(&staging)
+                .with_context(|| format!("failed to create {}", staging.display()))
+                .map_err(bundle_error)?;
+            hooks::run(&format!("{} pre-bundle", format), command, job.package.manifest_dir(), &env)?;
+            let resources = config::collect_staged(&staging, &ctx.config.resources)
+                .context("invalid files staged by the pre-bundle hook")
+                .map_err(bundle_error)?;
+            staged_config = Config {
+                resources,
+                ..ctx.config.clone()
+            };
+            staged_ctx = Context {
+                config: &staged_config,
+                ..ctx.clone()
+            };
+            &staged_ctx
+        }
+        None => ctx,
+    };
+
+    let fingerprint = cache::fingerprint(ctx, format, binary_sha256, layout.source_date);
+    if !layout.force && !binary_sha256.is_empty() {
+        if let Some(artifacts) = layout.cache.lookup(package, target, format, &fingerprint) {
+            status!("{} {} bundle is up to date", package, format);
+            events::emit(&Event::CacheHit { package, format, target });
+            // The cached artifacts are the ones the post-bundle hook already processed, e.g.
+            // signed in place, so running it again could sign them twice
+            if hooks.post_bundle.as_ref().and_then(|h| h.command(format)).is_some() {
+                status!("Skipping {} post-bundle hook for the cached bundle, use --force to rerun it", format);
+            }
+            return Ok(artifacts);
+        }
+    }
+    let artifacts = run_bundler(ctx, bundler)?;
+    if let Some(command) = hooks.post_bundle.as_ref().and_then(|h| h.command(format)) {
+        let paths: Vec<String> = artifacts.iter().map(|a| a.path.display().to_string()).collect();
+        env.push(("SLINT_BUNDLER_ARTIFACT", paths.first().cloned().unwrap_or_default()));
+        env.push(("SLINT_BUNDLER_ARTIFACTS", paths.join("\n")));
+        hooks::run(&format!("{} post-bundle", format), command, job.package.manifest_dir(), &env)?;
+    }
+    // Stored after the post-bundle hook so artifacts it signs in place stay cached
+    if let Err(e) = layout.cache.store(package, target, format, &fingerprint, &artifacts) {
+        events::warning(format_args!("failed to update the bundle cache: {:#}", e));
     }
+    Ok(artifacts)
 }
 
+/// Run a single bundler, keeping a structured error if it raised one.
+fn run_bundler(ctx: &Context, bundler: &dyn Bundler) -> Result<Vec<Artifact>> {
+    let result = match ctx.binaries.iter().find(|b| !b.path.exists()) {
+        None => bundler.bundle(ctx),
+        Some(missing) => Err(anyhow::anyhow!(
+            "binary not found at {}. Make sure `cargo build` ran successfully",
+            missing.path.display()
+        )),
+    };
+    result.map_err(|e| match e.downcast::<Error>() {
+        Ok(e) => e,
+        Err(e) => Error::Bundle {
+            bundle: bundler.name().to_string(),
+            error: e,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Context for bundling `config` for x86_64 Linux, with the binary at `dir/<name>` and
+    /// the bundles going to `dir/bundle`.
+    pub(crate) fn context<'a>(config: &'a Config, dir: &Path) -> Context<'a> {
+        let binary = dir.join(&config.name);
+        Context {
+            config,
+            binary: binary.clone(),
+            binaries: vec![Binary {
+                path: binary,
+                launcher: true,
+                aliases: Vec::new(),
+            }],
+            bundle_dir: dir.join("bundle"),
+            icons_dir: dir.join("icons"),
+            threads: 1,
+            target: "x86_64-unknown-linux-gnu".to_string(),
+            git_sha: "0000000".to_string(),
+            arch: Arch::X86_64,
+            mtime: 1_700_000_000,
+        }
+    }
+}
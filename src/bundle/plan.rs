@@ -0,0 +1,56 @@
+use std::path::PathBuf;
+use crate::events::status;
+
+/// What a bundler would do for one package, as printed by `build --dry-run`.
+#[derive(Clone, Debug, Default)]
+pub struct Plan {
+    /// Files placed into the package, in installation order.
+    pub files: Vec<PlannedFile>,
+    /// External programs the bundler runs.
+    pub tools: Vec<&'static str>,
+    /// Files the bundler would write.
+    pub artifacts: Vec<PathBuf>,
+}
+
+#[derive(Clone, Debug)]
+pub struct PlannedFile {
+    /// Install path inside the package, e.g. `usr/bin/app`.
+    pub dest: PathBuf,
+    /// Where the contents come from: a path on disk, generated data or a link target.
+    pub source: String,
+    /// Permission bits; `None` for formats without Unix modes.
+    pub mode: Option<u32>,
+}
+
+impl PlannedFile {
+    pub fn new(dest: impl Into<PathBuf>, source: impl Into<String>, mode: Option<u32>) -> Self {
+        Self {
+            dest: dest.into(),
+            source: source.into(),
+            mode,
+        }
+    }
+}
+
+/// Print the staged files and artifacts of one bundle.
+pub fn print_plan(title: &str, plan: &Plan) {
+    status!("{}", title);
+    for file in &plan.files {
+        let mode = file.mode.map(|m| format!("{:04o}", m)).unwrap_or_else(|| "-".to_string());
+        status!("    {:>4}  {}  <- {}", mode, file.dest.display(), file.source);
+    }
+    for artifact in &plan.artifacts {
+        status!("    => {}", artifact.display());
+    }
+}
+
+/// Print whether each tool is on `PATH`.
+pub fn print_tools(tools: &[&str]) {
+    let width = tools.iter().map(|t| t.len()).max().unwrap_or(0);
+    for tool in tools {
+        match which::which(tool) {
+            Ok(path) => status!("  {:width$}  {}", tool, path.display(), width = width),
+            Err(_) => status!("  {:width$}  not found", tool, width = width),
+        }
+    }
+}
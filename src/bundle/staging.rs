@@ -0,0 +1,152 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::os::unix::fs::{symlink, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Where the contents of a staged file come from.
+#[derive(Clone, Debug)]
+pub enum Source {
+    /// Read from disk when the tree is serialized.
+    Path(PathBuf),
+    /// Generated in memory, e.g. a desktop file or a re-encoded icon.
+    Bytes(Vec<u8>),
+}
+
+impl Source {
+    pub fn read(&self) -> io::Result<Vec<u8>> {
+        match self {
+            Source::Path(path) => fs::read(path),
+            Source::Bytes(bytes) => Ok(bytes.clone()),
+        }
+    }
+
+    /// Size in bytes without reading the file.
+    pub fn size(&self) -> io::Result<u64> {
+        match self {
+            Source::Path(path) => fs::metadata(path).map(|m| m.len()),
+            Source::Bytes(bytes) => Ok(bytes.len() as u64),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum EntryKind {
+    Dir,
+    File(Source),
+    /// Symbolic link pointing at the given path.
+    Symlink(PathBuf),
+}
+
+/// Owner recorded in the archive; installed files belong to root unless stated otherwise.
+#[derive(Clone, Debug)]
+pub struct Owner {
+    pub user: String,
+    pub group: String,
+    pub uid: u64,
+    pub gid: u64,
+}
+
+impl Default for Owner {
+    fn default() -> Self {
+        Self {
+            user: "root".to_string(),
+            group: "root".to_string(),
+            uid: 0,
+            gid: 0,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub kind: EntryKind,
+    /// Permission bits, e.g. `0o755`.
+    pub mode: u32,
+    pub owner: Owner,
+}
+
+/// Filesystem layout of a package, built once and serialized by every format.
+/// Paths are relative to the installation root, e.g. `usr/bin/app`.
+#[derive(Clone, Debug)]
+pub struct StagedTree {
+    entries: BTreeMap<PathBuf, Entry>,
+    /// Modification time given to every entry, in seconds since the epoch.
+    pub mtime: u64,
+}
+
+impl Default for StagedTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StagedTree {
+    pub fn new() -> StagedTree {
+        StagedTree {
+            entries: BTreeMap::new(),
+            mtime: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        }
+    }
+
+    /// Add a directory and any missing parents.
+    pub fn add_dir(&mut self, path: impl AsRef<Path>) -> &mut Entry {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            self.add_dir(parent);
+        }
+        self.entries.entry(path.to_path_buf()).or_insert(Entry {
+            kind: EntryKind::Dir,
+            mode: 0o755,
+            owner: Owner::default(),
+        })
+    }
+
+    /// Add a regular file, replacing any earlier entry at `path`.
+    pub fn add_file(&mut self, path: impl AsRef<Path>, source: Source, mode: u32) -> &mut Entry {
+        self.insert(path.as_ref(), EntryKind::File(source), mode)
+    }
+
+    pub fn add_symlink(&mut self, path: impl AsRef<Path>, target: impl Into<PathBuf>) -> &mut Entry {
+        self.insert(path.as_ref(), EntryKind::Symlink(target.into()), 0o777)
+    }
+
+    fn insert(&mut self, path: &Path, kind: EntryKind, mode: u32) -> &mut Entry {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            self.add_dir(parent);
+        }
+        let entry = Entry {
+            kind,
+            mode,
+            owner: Owner::default(),
+        };
+        self.entries.insert(path.to_path_buf(), entry);
+        self.entries.get_mut(path).expect("entry was just inserted")
+    }
+
+    /// Entries sorted by path, so parents always come before their children.
+    pub fn entries(&self) -> impl Iterator<Item = (&Path, &Entry)> {
+        self.entries.iter().map(|(path, entry)| (path.as_path(), entry))
+    }
+
+    /// Materialize the tree below `root`, e.g. to build an AppDir.
+    pub fn write_to(&self, root: &Path) -> io::Result<()> {
+        fs::create_dir_all(root)?;
+        for (path, entry) in self.entries() {
+            let dest = root.join(path);
+            match &entry.kind {
+                EntryKind::Dir => fs::create_dir_all(&dest)?,
+                EntryKind::File(source) => {
+                    fs::write(&dest, source.read()?)?;
+                    fs::set_permissions(&dest, fs::Permissions::from_mode(entry.mode))?;
+                }
+                EntryKind::Symlink(target) => symlink(target, &dest)?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,77 @@
+use std::fs;
+use std::path::PathBuf;
+use crate::events::status;
+
+/// Result of producing one bundle for one package and target.
+pub struct Outcome {
+    pub package: String,
+    /// Target triple, or `host` when building for the current machine.
+    pub target: String,
+    pub bundle: String,
+    pub result: Result<PathBuf, String>,
+}
+
+/// Print a table of every artifact with its size, followed by the failures.
+pub fn print_summary(outcomes: &[Outcome]) {
+    if outcomes.is_empty() {
+        return;
+    }
+    let rows: Vec<[String; 5]> = outcomes
+        .iter()
+        .map(|o| {
+            let (artifact, size) = match &o.result {
+                Ok(path) => (
+                    path.display().to_string(),
+                    fs::metadata(path).map(|m| format_size(m.len())).unwrap_or_else(|_| "?".to_string()),
+                ),
+                Err(reason) => (reason.clone(), "FAILED".to_string()),
+            };
+            [o.package.clone(), o.target.clone(), o.bundle.clone(), size, artifact]
+        })
+        .collect();
+
+    let header = ["Package", "Target", "Bundle", "Size", "Artifact"];
+    let mut widths = header.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    status!();
+    print_row(&header.map(String::from), &widths);
+    print_row(&widths.map(|w| "-".repeat(w)), &widths);
+    for row in &rows {
+        print_row(row, &widths);
+    }
+
+    let failures = outcomes.iter().filter(|o| o.result.is_err()).count();
+    if failures > 0 {
+        status!("\n{} of {} bundles failed", failures, outcomes.len());
+    }
+}
+
+fn print_row(cells: &[String; 5], widths: &[usize; 5]) {
+    let line: Vec<String> = cells
+        .iter()
+        .zip(widths)
+        .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+        .collect();
+    status!("{}", line.join("  ").trim_end());
+}
+
+/// 1536 -> "1.5 KiB"
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
@@ -1,52 +1,43 @@
+use std::path::{Path, PathBuf};
+use crate::bundle::plan::PlannedFile;
+use crate::bundle::Context;
+use crate::config::{Config, InstallRoot, Resource};
 
-use std::fs;
 mod msix_bundle;
-pub use msix_bundle::bundle_msix;
+pub use msix_bundle::Msix;
 mod msi;
-pub use msi::bundle_msi;
+pub use msi::Msi;
 mod nsis;
-pub use nsis::bundle_nsis;
+pub use nsis::Nsis;
 
-/// Helper: Make package name look nicer by replacing hyphens with spaces and capitalizing words
-pub fn prettify_package_name(name: &str) -> String {
-    name.split('-')
-        .map(|word| {
-            let mut chars = word.chars();
-            match chars.next() {
-                None => String::new(),
-                Some(first) => first.to_uppercase().chain(chars).collect(),
-            }
-        })
-        .collect::<Vec<String>>()
-        .join(" ")
+/// Install path of a resource below the application folder; `$BIN` and `$DATA` both map to
+/// the folder itself, `$DOC` and `$ETC` to its `doc` and `etc` subfolders.
+fn resource_path(resource: &Resource) -> PathBuf {
+    match resource.root {
+        InstallRoot::Bin | InstallRoot::Data => resource.path.clone(),
+        InstallRoot::Doc => Path::new("doc").join(&resource.path),
+        InstallRoot::Etc => Path::new("etc").join(&resource.path),
+    }
 }
-/// Helper: Extract name and version from Cargo.toml
-pub fn read_package_metadata() -> (String, String) {
-    let manifest = fs::read_to_string("Cargo.toml").expect("Failed to read Cargo.toml");
-    let package_name = manifest
-        .lines()
-        .find_map(|line| {
-            if line.trim_start().starts_with("name") {
-                line.split('=').nth(1).map(|v| v.trim().trim_matches('"').to_string())
-            } else {
-                None
-            }
-        })
-        .expect("Could not find package name in Cargo.toml");
 
-    let version = manifest
-        .lines()
-        .find_map(|line| {
-            if line.trim_start().starts_with("version") {
-                line.split('=').nth(1).map(|v| v.trim().trim_matches('"').to_string())
-            } else {
-                None
-            }
-        })
-        .unwrap_or_else(|| "0.1.0".to_string());
-
-    (package_name, version)
+/// `a/b/c` -> `a\b\c`, for installer scripts.
+fn windows_path(path: &Path) -> String {
+    path.iter().map(|c| c.to_string_lossy()).collect::<Vec<_>>().join("\\")
 }
 
+/// Every binary of the bundle, installed into `install_dir`.
+fn planned_binaries(ctx: &Context, install_dir: &Path) -> Vec<PlannedFile> {
+    ctx.binaries
+        .iter()
+        .map(|b| PlannedFile::new(install_dir.join(b.file_name()), b.path.display().to_string(), None))
+        .collect()
+}
 
-
+/// The configured resources as installed below `install_dir`.
+fn planned_resources(config: &Config, install_dir: &Path) -> Vec<PlannedFile> {
+    config
+        .resources
+        .iter()
+        .map(|r| PlannedFile::new(install_dir.join(resource_path(r)), r.source.display().to_string(), None))
+        .collect()
+}
@@ -1,62 +1,145 @@
 use std::process::Command;
 use std::fs;
 use std::path::{Path, PathBuf};
-use crate::bundle::windows::{read_package_metadata, prettify_package_name};
+use anyhow::{bail, Context as _, Result};
+use crate::bundle::bundler::{Artifact, Bundler};
+use crate::bundle::plan::{Plan, PlannedFile};
+use crate::bundle::windows::{planned_binaries, planned_resources, resource_path, windows_path};
+use crate::bundle::Context;
+use crate::error::Error;
+use crate::events::{self, status};
 
 
-/// Create an MSI installer using WiX Toolset (if available)
-pub fn bundle_msi() {
-    println!("Creating MSI package...");
+/// MSI installer built with the WiX Toolset (if available)
+pub struct Msi;
 
-    let (package_name, version) = read_package_metadata();
+impl Bundler for Msi {
+    fn name(&self) -> &'static str {
+        "msi"
+    }
+
+    fn supported_hosts(&self) -> &'static [&'static str] {
+        &["windows"]
+    }
+
+    fn plan(&self, ctx: &Context) -> Result<Plan> {
+        let config = ctx.config;
+        let install_dir = Path::new("ProgramFiles").join(&config.name);
+        let mut files = planned_binaries(ctx, &install_dir);
+        files.push(PlannedFile::new(
+            install_dir.join(format!("{}_{}.ico", config.name, config.version.msi()?)),
+            ctx.icons_dir.join("icon.ico").display().to_string(),
+            None,
+        ));
+        files.extend(planned_resources(config, &install_dir));
+        Ok(Plan {
+            files,
+            tools: vec!["wix.exe"],
+            artifacts: vec![out_path(ctx)?],
+        })
+    }
+
+    fn bundle(&self, ctx: &Context) -> Result<Vec<Artifact>> {
+        bundle_msi(ctx)
+    }
+}
+
+fn bundle_msi(ctx: &Context) -> Result<Vec<Artifact>> {
+    status!("Creating MSI package...");
+
+    let config = ctx.config;
+    let release_bin = ctx.binary.as_path();
+    let bundle_dir = ctx.bundle_dir.as_path();
+
+    let package_name = config.name.clone();
+    let version = config.version.msi()?;
     
 
-    let out_dir = std::path::Path::new("target")
-        .join("release")
-        .join("bundle")
-        .join("msi");
-    std::fs::create_dir_all(&out_dir).expect("failed to create output dir");
-    let out_path = out_dir.join(format!("{}_{}.msi", package_name, version));
-    let _ = prettify_package_name(&package_name);
+    let out_dir = bundle_dir.join("msi");
+    fs::create_dir_all(&out_dir).context("failed to create output dir")?;
+    let out_path = out_path(ctx)?;
     // Check for WiX Toolset (wix.exe)
     if which::which("wix.exe").is_err() {
-        eprintln!("WiX Toolset (wix.exe) not found in PATH. Skipping MSI build.");
-        return;
+        return Err(Error::missing_tool("wix.exe").into());
     }
-    let release_bin = std::path::Path::new("target")
-        .join("release")
-        .join(format!("{}.exe", package_name));
-    let bin_dest = Path::new("target")
-        .join("release")
-        .join("bundle")
-        .join("msi")
-        .join(format!("{}.exe", package_name));
-    
     if !release_bin.exists() {
-        eprintln!(
-            "Release binary not found at {}. Run `cargo build --release` first.",
+        bail!(
+            "release binary not found at {}. Run `cargo build` first",
             release_bin.display()
         );
-        return;
+    }
+    let exe = release_bin
+        .file_name()
+        .with_context(|| format!("{} has no file name", release_bin.display()))?
+        .to_string_lossy()
+        .to_string();
+    let bin_dest = out_dir.join(&exe);
+
+    let ico_src = ctx.icons_dir.join("icon.ico");
+    let icon = bundle_dir.join("msi").join(format!("{}_{}.ico", package_name, version));
+
+    fs::copy(&ico_src, &icon)
+        .with_context(|| format!("failed to copy {}", ico_src.display()))?;
+    fs::copy(release_bin, &bin_dest)
+        .with_context(|| format!("failed to copy {}", release_bin.display()))?;
+    // Further binaries go next to the main one, with a Start menu entry per launcher
+    let mut component_refs = String::new();
+    let mut components = String::new();
+    for (i, binary) in ctx.binaries.iter().enumerate().filter(|(_, b)| b.path != ctx.binary) {
+        let file = binary.file_name();
+        fs::copy(&binary.path, out_dir.join(&file))
+            .with_context(|| format!("failed to copy {}", binary.path.display()))?;
+        component_refs.push_str(&format!("            <ComponentRef Id=\"Binary{}\" />\n", i));
+        components.push_str(&format!(
+            r#"        <Component Id="Binary{i}" Directory="INSTALLFOLDER">
+            <File Id="BinaryFile{i}" Source="{file}" />
+        </Component>
+"#,
+            i = i,
+            file = file,
+        ));
+    }
+    let mut start_menu_shortcuts = String::new();
+    for (i, (binary, name)) in ctx.launchers().enumerate() {
+        start_menu_shortcuts.push_str(&format!(
+            r#"                    <Shortcut Id="startMenuShortcut{i}" Name="{name}" Description="Launch {name}" Target="[INSTALLFOLDER]{exe}" WorkingDirectory="INSTALLFOLDER" Icon="ProductIcon" />
+"#,
+            i = i,
+            name = name,
+            exe = binary.file_name(),
+        ));
+    }
+
+    // One component per resource, placed into a subfolder of INSTALLFOLDER where needed
+    for (i, resource) in config.resources.iter().enumerate() {
+        let dest = resource_path(resource);
+        let file = dest
+            .file_name()
+            .with_context(|| format!("resource destination {} has no file name", dest.display()))?;
+        let subdirectory = dest
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .map(|p| format!(r#" Subdirectory="{}""#, windows_path(p)))
+            .unwrap_or_default();
+        component_refs.push_str(&format!("            <ComponentRef Id=\"Resource{}\" />\n", i));
+        components.push_str(&format!(
+            r#"        <Component Id="Resource{i}" Directory="INSTALLFOLDER"{sub}>
+            <File Id="ResourceFile{i}" Source="{src}" Name="{file}" />
+        </Component>
+"#,
+            i = i,
+            sub = subdirectory,
+            src = resource.source.display(),
+            file = file.to_string_lossy(),
+        ));
     }
 
-    let ico_src = Path::new("icons").join("icon.ico");
-    let icon = Path::new("target").join("release").join("bundle").join("msi").join(format!("{}_{}.ico", package_name, version));
-
-    fs::copy(
-        PathBuf::from(ico_src.clone()),
-        PathBuf::from(icon.clone()),
-    ).expect("Failed to copy");
-    fs::copy(
-        PathBuf::from(release_bin.clone()),
-        PathBuf::from(bin_dest.clone()),
-    ).expect("Failed to copy");
     // Create a temporary .wxs file for WiX
     
-    let wxs_path = Path::new("target").join("release").join("bundle").join("msi").join("installer.wxs");
+    let wxs_path = bundle_dir.join("msi").join("installer.wxs");
     let wxs_content = format!(
         r#"<Wix xmlns="http://wixtoolset.org/schemas/v4/wxs">
-    <Package Name="{name}" Language="1033" Version="{version}" Manufacturer="{name}" UpgradeCode="11111111-2222-3333-4444-555555555555">
+    <Package Name="{name}" Language="1033" Version="{version}" Manufacturer="{publisher}" UpgradeCode="{upgrade_code}">
 
         <!-- Icon embedded for shortcuts and ARP -->
         <Icon Id="ProductIcon" SourceFile="{name}_{version}.ico" />
@@ -67,32 +150,31 @@ pub fn bundle_msi() {
             <ComponentRef Id="AppIconComponent" />
             <ComponentRef Id="DesktopShortcutComponent" />
             <ComponentRef Id="StartMenuShortcutComponent" />
-        </Feature>
+{component_refs}        </Feature>
 
         <StandardDirectory Id="ProgramFilesFolder">
             <Directory Id="INSTALLFOLDER" Name="{name}">
                 <Component Id="MainExecutable">
-                    <File Id="AppBinary" Source="{name}.exe" KeyPath="yes" />
+                    <File Id="AppBinary" Source="{exe}" KeyPath="yes" />
                 </Component>
                 <Component Id="AppIconComponent">
                     <File Id="AppIconFile" Source="{name}_{version}.ico" />
                 </Component>
             </Directory>
         </StandardDirectory>
-
+{components}
         <StandardDirectory Id="DesktopFolder">
             <Component Id="DesktopShortcutComponent">
-                <Shortcut Id="desktopShortcut" Name="{pretty_name}" Description="Launch {pretty_name}" Target="[INSTALLFOLDER]{name}.exe" WorkingDirectory="INSTALLFOLDER" Icon="ProductIcon" />
+                <Shortcut Id="desktopShortcut" Name="{pretty_name}" Description="Launch {pretty_name}" Target="[INSTALLFOLDER]{exe}" WorkingDirectory="INSTALLFOLDER" Icon="ProductIcon" />
                 <RemoveFolder Id="RemoveDesktopFolder" On="uninstall" />
-                <RegistryValue Root="HKLM" Key="Software\\slint-rust-template" Name="installed" Type="integer" Value="1" KeyPath="yes" />
+                <RegistryValue Root="HKLM" Key="Software\\{name}" Name="installed" Type="integer" Value="1" KeyPath="yes" />
             </Component>
         </StandardDirectory>
 
         <StandardDirectory Id="ProgramMenuFolder">
             <Directory Id="ApplicationProgramsFolder" Name="{pretty_name}">
                 <Component Id="StartMenuShortcutComponent">
-                    <Shortcut Id="startMenuShortcut" Name="{pretty_name}" Description="Launch {pretty_name}" Target="[INSTALLFOLDER]{name}.exe" WorkingDirectory="INSTALLFOLDER" Icon="ProductIcon" />
-                    <RemoveFolder Id="RemoveProgramMenuFolder" On="uninstall" />
+{start_menu_shortcuts}                    <RemoveFolder Id="RemoveProgramMenuFolder" On="uninstall" />
                     <RegistryValue Root="HKLM" Key="Software\\{name}" Name="startmenu" Type="integer" Value="1" KeyPath="yes" />
                 </Component>
             </Directory>
@@ -102,23 +184,36 @@ pub fn bundle_msi() {
 </Wix>
 "#,
         name = package_name,
-        pretty_name = prettify_package_name(&package_name),
+        exe = exe,
+        pretty_name = config.display_name,
+        publisher = config.publisher,
+        upgrade_code = config.upgrade_code(),
         version = version,
+        component_refs = component_refs,
+        components = components,
+        start_menu_shortcuts = start_menu_shortcuts,
     );
-    std::fs::write(&wxs_path, wxs_content).expect("failed to write wxs file");
+    fs::write(&wxs_path, wxs_content).context("failed to write wxs file")?;
 
-    Command::new("cd target/release/bundle/msi");
     // Build the MSI using wix.exe
     let status = Command::new("wix.exe")
-        //.current_dir(&out_dir_canonical)
+        .current_dir(&out_dir)
         .arg("build")
         .arg("installer.wxs")
+        .arg("-o")
+        .arg(out_path.file_name().context("MSI output path has no file name")?)
+        .stdout(events::child_stdout())
         .status()
-        .expect("failed to run wix.exe");
+        .context("failed to run wix.exe")?;
     if !status.success() {
-        eprintln!("wix.exe build failed");
-        return;
+        bail!("wix.exe build failed ({})", status);
     }
 
-    println!("Created {}", out_path.display());
-}
\ No newline at end of file
+    status!("Created {}", out_path.display());
+    Ok(vec![Artifact { path: out_path }])
+}
+
+fn out_path(ctx: &Context) -> Result<PathBuf> {
+    let version = ctx.config.version.msi()?;
+    Ok(ctx.artifact_path("msi", "{name}_{version}_{arch}", &version, ctx.arch.msix(), ".msi"))
+}
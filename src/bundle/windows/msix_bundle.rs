@@ -1,84 +1,117 @@
-use msix::{AppxManifest, manifest::Applications, manifest::Application/*, AppxManifest::Capabilities*/, manifest::Capability, manifest::Dependencies, manifest::Identity, Msix, manifest::Properties, manifest::Resources, manifest::Resource};
+use msix::{AppxManifest, manifest::Applications, manifest::Application/*, AppxManifest::Capabilities*/, manifest::Capability, manifest::Dependencies, manifest::Identity, Msix as MsixBuilder, manifest::Properties, manifest::Resources, manifest::Resource};
 use msix::manifest::VisualElements;
 use std::path::Path;
 use std::path::PathBuf;
 use std::fs;
-use crate::bundle::windows::read_package_metadata;
-/// Create an MSIX package using MakeAppx (if available)
-pub fn bundle_msix() {
-    println!("Creating MSIX package...");
+use anyhow::{anyhow, bail, Context as _, Result};
+use crate::bundle::bundler::{Artifact, Bundler};
+use crate::bundle::plan::{Plan, PlannedFile};
+use crate::bundle::windows::{planned_binaries, planned_resources, resource_path};
+use crate::bundle::{Arch, Context};
+use crate::events::{self, status};
+
+/// MSIX package built with the msix crate
+pub struct Msix;
+
+impl Bundler for Msix {
+    fn name(&self) -> &'static str {
+        "msix"
+    }
+
+    fn supported_hosts(&self) -> &'static [&'static str] {
+        &["windows"]
+    }
+
+    fn plan(&self, ctx: &Context) -> Result<Plan> {
+        let install_dir = Path::new("VFS").join(program_files(ctx)).join(&ctx.config.name);
+        let mut files = planned_binaries(ctx, &install_dir);
+        files.push(PlannedFile::new("Assets/Logo.png", ctx.icons_dir.join("icon.png").display().to_string(), None));
+        files.push(PlannedFile::new("Assets/SmallLogo.png", ctx.icons_dir.join("32x32.png").display().to_string(), None));
+        files.extend(planned_resources(ctx.config, &install_dir));
+        Ok(Plan {
+            files,
+            tools: Vec::new(),
+            artifacts: vec![out_path(ctx)?],
+        })
+    }
+
+    fn bundle(&self, ctx: &Context) -> Result<Vec<Artifact>> {
+        bundle_msix(ctx)
+    }
+}
+
+fn bundle_msix(ctx: &Context) -> Result<Vec<Artifact>> {
+    status!("Creating MSIX package...");
+
+    let config = ctx.config;
+    let release_bin = ctx.binary.as_path();
+    let bundle_dir = ctx.bundle_dir.as_path();
 
-    let (package_name, version) = read_package_metadata();
-    let release_bin = Path::new("target").join("release").join(format!("{}.exe", package_name));
+    let package_name = config.name.clone();
+    let version = config.version.msix()?;
 
     if !release_bin.exists() {
-        eprintln!(
-            "Release binary not found at {}. Run `cargo build --release` first.",
+        bail!(
+            "release binary not found at {}. Run `cargo build` first",
             release_bin.display()
         );
-        return;
     }
 
-    let out_dir = Path::new("target").join("release").join("bundle").join("msix");
-    if let Err(e) = fs::create_dir_all(&out_dir) {
-        eprintln!("Failed to create output dir: {e}");
-        return;
-    }
+    let out_dir = bundle_dir.join("msix");
+    fs::create_dir_all(&out_dir).context("failed to create output dir")?;
 
-    let out_path = out_dir.join(format!("{}_{}.msix", package_name, version));
+    let out_path = out_path(ctx)?;
 
     // Create staging folder
     let staging_dir = out_dir.join("staging");
     if staging_dir.exists() {
-        if let Err(e) = fs::remove_dir_all(&staging_dir) {
-            eprintln!("Failed to remove old staging dir: {e}");
-            return;
-        }
-    }
-    if let Err(e) = fs::create_dir_all(&staging_dir) {
-        eprintln!("Failed to create staging dir: {e}");
-        return;
+        fs::remove_dir_all(&staging_dir).context("failed to remove old staging dir")?;
     }
+    fs::create_dir_all(&staging_dir).context("failed to create staging dir")?;
 
-    // Copy binary into VFS structure
+    // Copy binaries into VFS structure
+    let program_files = program_files(ctx);
     let vfs_bin_dir = staging_dir
         .join("VFS")
-        .join("ProgramFilesX64")
+        .join(program_files)
         .join(&package_name);
-    if let Err(e) = fs::create_dir_all(&vfs_bin_dir) {
-        eprintln!("Failed to create VFS path: {e}");
-        return;
+    fs::create_dir_all(&vfs_bin_dir).context("failed to create VFS path")?;
+    for binary in &ctx.binaries {
+        fs::copy(&binary.path, vfs_bin_dir.join(binary.file_name()))
+            .with_context(|| format!("failed to copy {}", binary.path.display()))?;
     }
-    if let Err(e) = fs::copy(&release_bin, vfs_bin_dir.join(release_bin.file_name().unwrap())) {
-        eprintln!("Failed to copy binary: {e}");
-        return;
+    for resource in &config.resources {
+        let dest = vfs_bin_dir.join(resource_path(resource));
+        fs::create_dir_all(dest.parent().unwrap()).context("failed to create resource dir")?;
+        fs::copy(&resource.source, &dest)
+            .with_context(|| format!("failed to copy {}", resource.source.display()))?;
     }
 
     // Dummy assets
     let assets_dir = staging_dir.join("Assets");
-    if let Err(e) = fs::create_dir_all(&assets_dir) {
-        eprintln!("Failed to create Assets dir: {e}");
-        return;
-    }
-    let source = PathBuf::from("icons/icon.png");
+    fs::create_dir_all(&assets_dir).context("failed to create Assets dir")?;
+    let source = ctx.icons_dir.join("icon.png");
     let _ = fs::copy(source, assets_dir.join("Logo.png"));
-    let source = PathBuf::from("icons/32x32.png");
+    let source = ctx.icons_dir.join("32x32.png");
     let _ = fs::copy(source, assets_dir.join("SmallLogo.png"));
+    // The zip entries take their timestamps from the staged files
+    set_mtimes(&staging_dir, filetime::FileTime::from_unix_time(ctx.mtime as i64, 0))
+        .context("failed to set staging timestamps")?;
     // --- Construct AppxManifest programmatically ---
     
     let mut manifest = AppxManifest::default();
 
     manifest.identity = Identity {
-        name: Some(format!("com.slint.{}", package_name)),
-        publisher: Some("CN=Slint Bundler".to_string()),
-        version: Some(format!("{}.0", version)),
-        processor_architecture: Some("x64".to_string()),
+        name: Some(config.identifier.clone()),
+        publisher: Some(format!("CN={}", config.publisher)),
+        version: Some(version),
+        processor_architecture: Some(ctx.arch.msix().to_string()),
     };
 
     manifest.properties = Properties {
-        display_name: Some(package_name.clone()),
-        publisher_display_name: Some("Slint Bundler".to_string()),
-        description: Some(format!("{} MSIX Package", package_name)),
+        display_name: Some(config.display_name.clone()),
+        publisher_display_name: Some(config.publisher.clone()),
+        description: Some(config.description.clone()),
         logo: Some("Assets\\Logo.png".to_string()),
     };
 
@@ -92,30 +125,26 @@ pub fn bundle_msix() {
         target_device_family: vec![],
     };
 
-    manifest.capabilities = vec![
-        Capability::Capability {
-            name: "internetClient".to_string(),
-        },
-        Capability::Device {
-            name: "microphone".to_string(),
-        },
-        Capability::Restricted {
-            name: "documentsLibrary".to_string(),
-        },
-    ];
+    manifest.capabilities = config
+        .windows
+        .capabilities
+        .iter()
+        .map(|name| Capability::Capability { name: name.clone() })
+        .collect();
 
     manifest.applications = Applications {
         application: vec![Application {
-            id: Some("io.github.RoseBlume.slintbundler".to_string()),
+            id: Some(config.identifier.clone()),
             executable: Some(format!(
-                "VFS\\ProgramFilesX64\\{}\\{}",
+                "VFS\\{}\\{}\\{}",
+                program_files,
                 package_name,
                 release_bin.file_name().unwrap().to_string_lossy()
             )),
             entry_point: Some("Windows.FullTrustApplication".to_string()),
             visual_elements: VisualElements {
-                display_name: Some(package_name.clone()),
-                description: Some("Rust app bundled by Slint".to_string()),
+                display_name: Some(config.display_name.clone()),
+                description: Some(config.description.clone()),
                 background_color: Some("transparent".to_string()),
                 logo_150x150: Some("Assets\\Logo.png".to_string()),
                 logo_44x44: Some("Assets\\SmallLogo.png".to_string()),
@@ -124,35 +153,43 @@ pub fn bundle_msix() {
         }],
     };
 
-    
-    
-    
-    //let manifest = <AppxManifest as std::default::Default>::default();
-
     // --- Build MSIX ---
-    println!("Building MSIX package with msix crate...");
+    status!("Building MSIX package with msix crate...");
 
-    let mut msix = match Msix::new(out_path.clone(), manifest, true) {
-        Ok(m) => m,
-        Err(e) => {
-            eprintln!("Failed to initialize Msix builder: {e}");
-            return;
-        }
-    };
+    let mut msix = MsixBuilder::new(out_path.clone(), manifest, true)
+        .map_err(|e| anyhow!("failed to initialize Msix builder: {e}"))?;
 
-    if let Err(e) = msix.add_directory(&staging_dir, Path::new("")) {
-        eprintln!("Failed to add directory to MSIX: {e}");
-        return;
-    }
+    msix.add_directory(&staging_dir, Path::new(""))
+        .map_err(|e| anyhow!("failed to add directory to MSIX: {e}"))?;
 
     if let Err(e) = msix.add_icon(&assets_dir.join("Logo.png")) {
-        eprintln!("Failed to add icon: {e}");
+        events::warning(format_args!("failed to add icon: {e}"));
     }
 
-    if let Err(e) = msix.finish(None) {
-        eprintln!("Failed to finalize MSIX package: {e}");
-        return;
-    }
+    msix.finish(None)
+        .map_err(|e| anyhow!("failed to finalize MSIX package: {e}"))?;
 
-    println!("Created {}", out_path.display());
-}
\ No newline at end of file
+    status!("Created {}", out_path.display());
+    Ok(vec![Artifact { path: out_path }])
+}
+
+fn set_mtimes(dir: &Path, mtime: filetime::FileTime) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            set_mtimes(&path, mtime)?;
+        }
+        filetime::set_file_mtime(&path, mtime)?;
+    }
+    Ok(())
+}
+
+/// VFS folder of the binary; 32-bit binaries live under Program Files (x86).
+fn program_files(ctx: &Context) -> &'static str {
+    if ctx.arch == Arch::I686 { "ProgramFilesX86" } else { "ProgramFilesX64" }
+}
+
+fn out_path(ctx: &Context) -> Result<PathBuf> {
+    let version = ctx.config.version.msix()?;
+    Ok(ctx.artifact_path("msix", "{name}_{version}_{arch}", &version, ctx.arch.msix(), ".msix"))
+}
@@ -1,59 +1,126 @@
 use std::process::Command;
+use std::collections::BTreeSet;
 use std::fs;
 use std::path::{Path, PathBuf};
-use crate::bundle::windows::{read_package_metadata, prettify_package_name};
+use anyhow::{bail, Context as _, Result};
+use crate::bundle::bundler::{Artifact, Bundler};
+use crate::bundle::plan::{Plan, PlannedFile};
+use crate::bundle::windows::{planned_binaries, planned_resources, resource_path, windows_path};
+use crate::bundle::Context;
+use crate::error::Error;
+use crate::events::{self, status};
 
-/*
-const NSIS_PATHS: &[&str] = &[
-    "C:\\Program Files (x86)\\NSIS",
-    "C:\\Program Files\\NSIS"
-];
-*/
+/// NSIS installer built with makensis (if available)
+pub struct Nsis;
 
+impl Bundler for Nsis {
+    fn name(&self) -> &'static str {
+        "nsis"
+    }
 
-pub fn bundle_nsis() {
-    println!("Creating NSIS installer...");
+    fn supported_hosts(&self) -> &'static [&'static str] {
+        &["windows"]
+    }
 
-    let (package_name, version) = read_package_metadata();
-    let release_bin = Path::new("target").join("release").join(format!("{}.exe", package_name));
+    fn plan(&self, ctx: &Context) -> Result<Plan> {
+        let config = ctx.config;
+        let install_dir = Path::new("$PROGRAMFILES").join(&config.name);
+        let mut files = planned_binaries(ctx, &install_dir);
+        files.push(PlannedFile::new(
+            install_dir.join(format!("{}_{}.ico", config.name, config.version)),
+            ctx.icons_dir.join("icon.ico").display().to_string(),
+            None,
+        ));
+        files.extend(planned_resources(config, &install_dir));
+        Ok(Plan {
+            files,
+            tools: vec!["makensis.exe"],
+            artifacts: vec![out_path(ctx)],
+        })
+    }
+
+    fn bundle(&self, ctx: &Context) -> Result<Vec<Artifact>> {
+        bundle_nsis(ctx)
+    }
+}
+
+fn bundle_nsis(ctx: &Context) -> Result<Vec<Artifact>> {
+    status!("Creating NSIS installer...");
+
+    let config = ctx.config;
+    let release_bin = ctx.binary.as_path();
+    let bundle_dir = ctx.bundle_dir.as_path();
+
+    let package_name = config.name.clone();
+    let version = config.version.to_string();
 
     if !release_bin.exists() {
-        eprintln!("Release binary not found at {}. Run `cargo build --release` first.", release_bin.display());
-        return;
+        bail!("release binary not found at {}. Run `cargo build` first", release_bin.display());
     }
 
-    let out_dir = Path::new("target").join("release").join("bundle").join("nsis");
-    fs::create_dir_all(&out_dir).expect("failed to create output dir");
-    let out = out_dir.join(format!("{}_{}.exe", package_name, version));
+    let out_dir = bundle_dir.join("nsis");
+    fs::create_dir_all(&out_dir).context("failed to create output dir")?;
+    let out = out_path(ctx);
     if which::which("makensis.exe").is_err() {
-        eprintln!("makensis.exe not found in PATH. Skipping NSIS build.");
-        return;
+        return Err(Error::missing_tool("makensis.exe").into());
     }
-    let ico = Path::new("icons").join("icon.ico");
-    let icon = Path::new("target").join("release").join("bundle").join("nsis").join(format!("{}_{}.ico", package_name, version));
-
-    fs::copy(
-        PathBuf::from(ico.clone()),
-        PathBuf::from(icon.clone()),
-    ).expect("Failed to copy");
-    let out_path = fs::canonicalize(&out).expect("");
-    let bin_path = fs::canonicalize(&release_bin).expect("");
-    let icon_path = fs::canonicalize(&icon).expect("");
-    /*
-    
-
-    // Define the destination file path
-    let mut file = fs::File::create(&out_path);
-    let destination = fs::canonicalize(&out_path).expect("");
-    println!("{}", destination.display());
-
-    // Copy the file
-    fs::copy(
-        PathBuf::from(bin_path.clone()),//.clone().expect("Failed to convert bin_path to PathBuf").to_path_buf(), 
-        PathBuf::from(destination),//.clone().expect("Failed to convert destination to PathBuf").to_path_buf()
-    ).expect("Failed to copy");
-    */
-    let _ = fs::File::create(&out_path);
+    let ico = ctx.icons_dir.join("icon.ico");
+    let icon_name = format!("{}_{}.ico", package_name, version);
+    let icon = bundle_dir.join("nsis").join(&icon_name);
+    let exe = release_bin
+        .file_name()
+        .with_context(|| format!("{} has no file name", release_bin.display()))?
+        .to_string_lossy();
+
+    fs::copy(&ico, &icon)
+        .with_context(|| format!("failed to copy {}", ico.display()))?;
+    // makensis writes the installer itself, so only resolve where it goes
+    let out_path = std::path::absolute(&out).context("failed to resolve installer path")?;
+    let bin_path = fs::canonicalize(release_bin).context("failed to resolve binary path")?;
+    let icon_path = fs::canonicalize(&icon).context("failed to resolve icon path")?;
+
+    // Further binaries go next to the main one, with a Start menu entry per launcher
+    let mut install_binaries = String::new();
+    let mut uninstall_binaries = String::new();
+    for binary in ctx.binaries.iter().filter(|b| b.path != ctx.binary) {
+        let path = fs::canonicalize(&binary.path)
+            .with_context(|| format!("failed to resolve {}", binary.path.display()))?;
+        install_binaries.push_str(&format!("    File \"{}\"\n", path.display()));
+        uninstall_binaries.push_str(&format!("    Delete \"$INSTDIR\\{}\"\n", binary.file_name()));
+    }
+    let mut start_menu = String::new();
+    for (binary, name) in ctx.launchers() {
+        start_menu.push_str(&format!(
+            "    CreateShortCut \"$SMPROGRAMS\\$StartMenuFolder\\{name}.lnk\" \"$INSTDIR\\{exe}\" \"\" \"$INSTDIR\\{icon}\"\n",
+            name = name,
+            exe = binary.file_name(),
+            icon = icon_name,
+        ));
+        uninstall_binaries.push_str(&format!("    Delete \"$SMPROGRAMS\\$StartMenuFolder\\{}.lnk\"\n", name));
+    }
+
+    // Install resources into their subfolders, and remove them again on uninstall,
+    // innermost folders first
+    let mut install_resources = String::new();
+    let mut uninstall_resources = String::new();
+    let mut resource_dirs = BTreeSet::new();
+    for resource in &config.resources {
+        let dest = resource_path(resource);
+        let dir = dest.parent().unwrap_or(Path::new(""));
+        let source = fs::canonicalize(&resource.source)
+            .with_context(|| format!("failed to resolve {}", resource.source.display()))?;
+        install_resources.push_str(&format!(
+            "    SetOutPath \"{}\"\n    File \"{}\"\n",
+            windows_path(&Path::new("$INSTDIR").join(dir)),
+            source.display()
+        ));
+        uninstall_resources.push_str(&format!("    Delete \"$INSTDIR\\{}\"\n", windows_path(&dest)));
+        resource_dirs.extend(dir.ancestors().filter(|d| !d.as_os_str().is_empty()).map(Path::to_path_buf));
+    }
+    for dir in resource_dirs.iter().rev() {
+        uninstall_resources.push_str(&format!("    RMDir \"$INSTDIR\\{}\"\n", windows_path(dir)));
+    }
+
     // Create a temporary NSIS script
     let tmp = out_dir;
     let nsis_script = tmp.join("installer.nsi");
@@ -69,6 +136,7 @@ Page Directory
 Page InstFiles
 InstallDirRegKey HKCU "Software\Modern UI Test" ""
 
+; There is no Start menu page, so both sections set the folder themselves
 Var StartMenuFolder
 RequestExecutionLevel admin
 !define MUI_STARTMENUPAGE_REGISTRY_ROOT "HKLM" 
@@ -76,43 +144,55 @@ RequestExecutionLevel admin
 !define MUI_STARTMENUPAGE_REGISTRY_VALUENAME "Start Menu Folder"
 
 Section
+    StrCpy $StartMenuFolder "{pretty_name}"
     SetOutPath "$INSTDIR"
     File "{bin}"
-    File "{ico}"
+{install_binaries}    File "{ico}"
+{install_resources}    SetOutPath "$INSTDIR"
     CreateDirectory "$SMPROGRAMS\$StartMenuFolder"
     CreateShortCut "$DESKTOP\{pretty_name}.lnk" "$INSTDIR\{exe}" "" "$INSTDIR\{name}_{version}.ico"
-    CreateShortCut "$SMPROGRAMS\$StartMenuFolder\{pretty_name}.lnk" "$INSTDIR\{exe}" "" "$INSTDIR\{name}_{version}.ico"
-    WriteUninstaller "$INSTDIR\uninstall.exe"
+{start_menu}    WriteUninstaller "$INSTDIR\uninstall.exe"
 SectionEnd
 
 Section "Uninstall"
+    StrCpy $StartMenuFolder "{pretty_name}"
     Delete "$INSTDIR\{exe}"
     Delete "$INSTDIR\{name}_{version}.ico"
     Delete "$INSTDIR\uninstall.exe"
-    Delete "$DESKTOP\{name}.lnk"
-    Delete "$SMPROGRAMS\$StartMenuFolder\{name}.lnk"
+{uninstall_binaries}{uninstall_resources}    Delete "$DESKTOP\{pretty_name}.lnk"
     RMDir "$SMPROGRAMS\$StartMenuFolder"
     RMDir "$INSTDIR"
     DeleteRegKey HKLM "Software\{name}"
 SectionEnd
         "#,
         name = package_name,
-        pretty_name = prettify_package_name(&package_name),
+        pretty_name = config.display_name,
         version = version,
         out = out_path.display(),
         bin = bin_path.display(),
-        exe = release_bin.file_name().unwrap().to_string_lossy(),
-        ico = icon_path.display()
+        exe = exe,
+        ico = icon_path.display(),
+        install_binaries = install_binaries,
+        uninstall_binaries = uninstall_binaries,
+        start_menu = start_menu,
+        install_resources = install_resources,
+        uninstall_resources = uninstall_resources,
     );
-    fs::write(&nsis_script, script_content).expect("failed to write NSIS script");
+    fs::write(&nsis_script, script_content).context("failed to write NSIS script")?;
     let status = Command::new("makensis.exe")
         .arg(&nsis_script)
+        .stdout(events::child_stdout())
         .status()
-        .expect("failed to run makensis.exe");
+        .context("failed to run makensis.exe")?;
     if !status.success() {
-        eprintln!("makensis.exe failed");
-        return;
+        bail!("makensis.exe failed ({})", status);
     }
 
-    println!("Created {}", out_path.display());
-}
\ No newline at end of file
+    status!("Created {}", out_path.display());
+    Ok(vec![Artifact { path: out_path }])
+}
+
+fn out_path(ctx: &Context) -> PathBuf {
+    let version = ctx.config.version.to_string();
+    ctx.artifact_path("nsis", "{name}_{version}_{arch}-setup", &version, ctx.arch.msix(), ".exe")
+}
@@ -0,0 +1,171 @@
+use std::path::PathBuf;
+use clap::{Args, Parser, Subcommand};
+use clap_complete::Shell;
+use crate::bundle::BuildOptions;
+use crate::events::MessageFormat;
+
+/// Build, bundle and package Slint applications for desktop and Android.
+#[derive(Debug, Parser)]
+#[command(name = "slint-bundler", version, propagate_version = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Create a new Slint project in the current directory
+    New,
+    /// Build and bundle the project
+    Build(BuildArgs),
+    /// Run the project in dev mode (auto-recompile on change)
+    Dev,
+    /// Generate application icons from a 1024x1024 PNG
+    Icon {
+        /// Source image, must be 1024x1024px
+        input: PathBuf,
+    },
+    /// Check environment setup
+    Doctor {
+        /// Try to find missing tools and add them to PATH
+        #[arg(long)]
+        fix: bool,
+        /// Output format for messages
+        #[arg(long, value_enum, default_value_t)]
+        message_format: MessageFormat,
+    },
+    /// Run an android subcommand
+    Android {
+        #[command(subcommand)]
+        command: AndroidCommand,
+    },
+    /// Print a shell completion script to stdout
+    Completions {
+        shell: Shell,
+    },
+    /// Write man pages for every command
+    Man {
+        /// Directory the pages are written to
+        #[arg(long, default_value = "target/man")]
+        out_dir: PathBuf,
+    },
+    /// Check the artifacts in a bundle directory against its manifest.json
+    Verify {
+        /// Directory containing manifest.json, defaults to the bundle folder of `--profile`
+        /// and `--target` in cargo's target directory
+        dir: Option<PathBuf>,
+        /// Cargo profile the bundles were built with
+        #[arg(long, default_value = "release")]
+        profile: String,
+        /// Target triple the bundles were built for
+        #[arg(long, value_name = "TRIPLE")]
+        target: Option<String>,
+    },
+    /// Print the metadata and files of a package and check its structure
+    Inspect {
+        /// A .deb, .rpm, .tar.zst, .tar.xz, .AppImage, AppDir .tar.gz or .msix file
+        file: PathBuf,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct BuildArgs {
+    /// Bundle types to create, e.g. `deb,rpm`. Defaults to every format of the host
+    #[arg(long, value_delimiter = ',', num_args = 1..)]
+    pub bundles: Option<Vec<String>>,
+    /// Cargo profile to build with
+    #[arg(long, default_value = "release")]
+    pub profile: String,
+    /// Package to bundle, may be repeated
+    #[arg(short, long = "package", value_name = "NAME")]
+    pub packages: Vec<String>,
+    /// Bundle every workspace member with a binary target
+    #[arg(long)]
+    pub workspace: bool,
+    /// Target triple to cross-compile for, may be repeated
+    #[arg(long = "target", value_name = "TRIPLE")]
+    pub target: Vec<String>,
+    /// Comma separated target triples; a failing target does not stop the others
+    #[arg(long, value_delimiter = ',', value_name = "TRIPLE,...")]
+    pub targets: Vec<String>,
+    /// Print the cargo invocations, staged files and artifact names without building anything
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Produce byte-identical bundles: timestamps come from `SOURCE_DATE_EPOCH` or the
+    /// last commit. On by default when `CI` is set
+    #[arg(long)]
+    pub reproducible: bool,
+    /// Rebuild every bundle even if the cache says its inputs are unchanged
+    #[arg(long)]
+    pub force: bool,
+    /// Number of bundles to create in parallel, defaults to the number of CPUs
+    #[arg(short, long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub jobs: Option<u32>,
+    /// Write bundles, checksums and the manifest here instead of `target/<profile>/bundle`.
+    /// Several targets get a subfolder each
+    #[arg(long, value_name = "DIR")]
+    pub out_dir: Option<PathBuf>,
+    /// Release channel for `{channel}` in the `artifact-name` template, e.g. `nightly`
+    #[arg(long, value_name = "NAME")]
+    pub channel: Option<String>,
+    /// Output format for messages
+    #[arg(long, value_enum, default_value_t)]
+    pub message_format: MessageFormat,
+}
+
+impl From<BuildArgs> for BuildOptions {
+    fn from(args: BuildArgs) -> Self {
+        let mut targets: Vec<String> = Vec::new();
+        for triple in args.target.into_iter().chain(args.targets) {
+            if !triple.is_empty() && !targets.contains(&triple) {
+                targets.push(triple);
+            }
+        }
+        BuildOptions {
+            bundles: args.bundles,
+            profile: args.profile,
+            packages: args.packages,
+            workspace: args.workspace,
+            targets,
+            dry_run: args.dry_run,
+            reproducible: args.reproducible || std::env::var_os("CI").is_some(),
+            force: args.force,
+            jobs: args.jobs.map(|n| n as usize).unwrap_or_else(|| {
+                std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+            }),
+            out_dir: args.out_dir,
+            channel: args.channel,
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AndroidCommand {
+    /// Initialize an android project
+    Init,
+    /// Build an android apk
+    Build {
+        /// Output format for messages
+        #[arg(long, value_enum, default_value_t)]
+        message_format: MessageFormat,
+    },
+    /// Run the project on an android device or emulator (auto-recompile on change)
+    Dev,
+    /// Manage the keystore used to sign bundles
+    Key {
+        #[command(subcommand)]
+        command: KeyCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum KeyCommand {
+    /// Generate a keystore to sign with
+    Generate {
+        keyfile: String,
+    },
+    /// Sign an apk bundle
+    Sign {
+        keystore: String,
+    },
+}
@@ -0,0 +1,734 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+use crate::project::{Package, Project};
+
+mod resources;
+mod version;
+
+pub use resources::{collect_staged, InstallRoot, Resource, ResourceConfig};
+pub use version::{Version, VersionSource};
+
+const MANIFEST: &str = "Cargo.toml";
+const LEGACY_CONFIG: &str = "slint-app.json";
+
+/// Placeholders an `artifact-name` template may use.
+pub const ARTIFACT_NAME_PLACEHOLDERS: &[&str] = &["name", "version", "arch", "target", "channel", "git_sha"];
+
+/// The `[package.metadata.slint-bundler]` table as written by the user.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct BundlerConfig {
+    pub identifier: Option<String>,
+    pub display_name: Option<String>,
+    pub publisher: Option<String>,
+    pub description: Option<String>,
+    pub homepage: Option<String>,
+    pub license: Option<String>,
+    pub categories: Vec<String>,
+    /// Target triples `build` bundles for when `--target`/`--targets` is not given.
+    pub targets: Vec<String>,
+    /// `git` to take the version from `git describe` instead of `Cargo.toml`.
+    pub version_source: VersionSource,
+    /// `[[bin]]` targets to package; empty means just the main one, as the launcher.
+    pub bins: Vec<BinConfig>,
+    /// File name template for every artifact, without the extension.
+    pub artifact_name: Option<String>,
+    /// Release channel for `{channel}` in `artifact-name`, e.g. `beta`.
+    pub channel: Option<String>,
+    pub linux: LinuxConfig,
+    pub deb: FormatConfig,
+    pub rpm: FormatConfig,
+    pub tar_zst: FormatConfig,
+    pub tar_xz: FormatConfig,
+    pub windows: WindowsConfig,
+    pub android: AndroidConfig,
+    /// Extra files to install, e.g. translations or licenses.
+    pub resources: Vec<ResourceConfig>,
+    pub hooks: HooksConfig,
+}
+
+/// One `[[package.metadata.slint-bundler.bins]]` entry.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BinConfig {
+    /// Name of the cargo `[[bin]]` target.
+    pub name: String,
+    /// Gets a desktop entry or Start menu shortcut.
+    #[serde(default)]
+    pub launcher: bool,
+    /// Further command names, installed as symlinks into `/usr/bin` on Linux.
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+/// Shell commands `build` runs around its steps, e.g. `[package.metadata.slint-bundler.hooks]`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct HooksConfig {
+    /// Before the package's binaries are built.
+    pub pre_build: Option<String>,
+    /// Before each bundle is created.
+    pub pre_bundle: Option<FormatHook>,
+    /// After each bundle is created, e.g. to sign it. Bundles reused from the cache
+    /// already went through it and skip it.
+    pub post_bundle: Option<FormatHook>,
+    /// After all of the package's bundles are created.
+    pub post_build: Option<String>,
+}
+
+/// A hook command for every format, or a table of commands keyed by format name.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum FormatHook {
+    All(String),
+    PerFormat(std::collections::BTreeMap<String, String>),
+}
+
+impl FormatHook {
+    /// The command to run for `format`, if any.
+    pub fn command(&self, format: &str) -> Option<&str> {
+        match self {
+            FormatHook::All(command) => Some(command),
+            FormatHook::PerFormat(commands) => commands.get(format).map(String::as_str),
+        }
+    }
+}
+
+/// Payload compression of a package format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    Gzip,
+    Xz,
+    Zstd,
+    Bzip2,
+    #[serde(rename = "none")]
+    Uncompressed,
+}
+
+impl Compression {
+    /// Name as written in the configuration.
+    pub fn name(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Xz => "xz",
+            Compression::Zstd => "zstd",
+            Compression::Bzip2 => "bzip2",
+            Compression::Uncompressed => "none",
+        }
+    }
+}
+
+/// Per-format settings, e.g. `[package.metadata.slint-bundler.tar-xz]`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct FormatConfig {
+    /// Algorithm for formats that support several; defaults to the format's usual one.
+    pub compression: Option<Compression>,
+    /// Algorithm specific level, e.g. 0-9 for xz or 1-22 for zstd.
+    pub compression_level: Option<i32>,
+    /// Algorithm for the `control.tar` of a .deb, independent of `compression`; defaults to gzip.
+    pub control_compression: Option<Compression>,
+}
+
+/// Settings shared by the deb, rpm, pacman and AppImage bundlers.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct LinuxConfig {
+    /// Debian archive section; derived from `categories` when unset.
+    pub section: Option<String>,
+    /// Debian priority.
+    pub priority: String,
+    /// Debian `Maintainer`, e.g. `Jane Doe <jane@example.com>`; defaults to the first
+    /// author with an email address.
+    pub maintainer: Option<String>,
+    /// Debian `Depends` entries, e.g. `libfontconfig1 (>= 2.13)`.
+    pub depends: Vec<String>,
+    /// Add the libraries the binaries link against to the deb `Depends` and rpm `Requires`.
+    pub auto_depends: bool,
+    pub shlibs: ShlibsConfig,
+    pub scripts: MaintainerScripts,
+    /// Markdown changelog for `changelog.Debian.gz`, relative to `Cargo.toml`; defaults to
+    /// `CHANGELOG.md` when there is one.
+    pub changelog: Option<PathBuf>,
+    /// Epoch for the deb and rpm versions, to recover from a version scheme change.
+    pub epoch: Option<u32>,
+}
+
+impl Default for LinuxConfig {
+    fn default() -> Self {
+        Self {
+            section: None,
+            priority: "optional".to_string(),
+            maintainer: None,
+            depends: Vec::new(),
+            auto_depends: true,
+            shlibs: ShlibsConfig::default(),
+            scripts: MaintainerScripts::default(),
+            changelog: None,
+            epoch: None,
+        }
+    }
+}
+
+/// Soname to package overrides per distribution, e.g. `[package.metadata.slint-bundler.linux.shlibs.deb]`.
+/// An empty package name drops the dependency.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ShlibsConfig {
+    pub deb: std::collections::BTreeMap<String, String>,
+    pub rpm: std::collections::BTreeMap<String, String>,
+}
+
+/// Debian maintainer scripts, paths relative to `Cargo.toml`, e.g.
+/// `[package.metadata.slint-bundler.linux.scripts]`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MaintainerScripts {
+    pub preinst: Option<PathBuf>,
+    pub postinst: Option<PathBuf>,
+    pub prerm: Option<PathBuf>,
+    pub postrm: Option<PathBuf>,
+}
+
+impl MaintainerScripts {
+    /// Each configured script with its name in the control archive.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &PathBuf)> {
+        [
+            ("preinst", &self.preinst),
+            ("postinst", &self.postinst),
+            ("prerm", &self.prerm),
+            ("postrm", &self.postrm),
+        ]
+        .into_iter()
+        .filter_map(|(name, path)| Some((name, path.as_ref()?)))
+    }
+
+    /// Make the script paths absolute, relative to `dir`, and check they exist.
+    fn resolve(&mut self, dir: &Path) -> Result<()> {
+        for path in [&mut self.preinst, &mut self.postinst, &mut self.prerm, &mut self.postrm]
+            .into_iter()
+            .flatten()
+        {
+            *path = dir.join(&*path);
+            if !path.is_file() {
+                bail!("maintainer script {} not found", path.display());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Settings for the MSI, NSIS and MSIX bundlers.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct WindowsConfig {
+    /// Stable MSI upgrade code; defaults to one derived from the identifier.
+    pub upgrade_code: Option<String>,
+    /// MSIX capabilities, e.g. `internetClient`.
+    pub capabilities: Vec<String>,
+}
+
+impl Default for WindowsConfig {
+    fn default() -> Self {
+        Self {
+            upgrade_code: None,
+            capabilities: vec!["internetClient".to_string()],
+        }
+    }
+}
+
+/// Settings for the generated gradle project.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct AndroidConfig {
+    pub min_sdk: u32,
+    pub target_sdk: u32,
+    /// Defaults to one derived from the version.
+    pub version_code: Option<u32>,
+}
+
+impl Default for AndroidConfig {
+    fn default() -> Self {
+        Self {
+            min_sdk: 21,
+            target_sdk: 33,
+            version_code: None,
+        }
+    }
+}
+
+/// Resolved project configuration consumed by the bundlers and the android initializer.
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub name: String,
+    pub version: Version,
+    pub version_source: VersionSource,
+    pub identifier: String,
+    pub display_name: String,
+    pub publisher: String,
+    pub description: String,
+    pub homepage: Option<String>,
+    pub license: Option<String>,
+    /// License text: Cargo's `license-file`, else a `LICENSE` or `COPYING` file next to `Cargo.toml`.
+    pub license_file: Option<PathBuf>,
+    pub authors: Vec<String>,
+    pub categories: Vec<String>,
+    pub targets: Vec<String>,
+    pub bins: Vec<BinConfig>,
+    /// `artifact-name` template; `None` keeps each format's conventional name.
+    pub artifact_name: Option<String>,
+    pub channel: String,
+    pub linux: LinuxConfig,
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    pub deb: FormatConfig,
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    pub rpm: FormatConfig,
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    pub tar_zst: FormatConfig,
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    pub tar_xz: FormatConfig,
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    pub windows: WindowsConfig,
+    pub android: AndroidConfig,
+    /// Files matched by the `resources` patterns.
+    pub resources: Vec<Resource>,
+    pub hooks: HooksConfig,
+}
+
+/// Defaults every member inherits from the workspace root manifest.
+#[derive(Clone, Debug, Default)]
+pub struct WorkspaceDefaults {
+    /// `[workspace.package]`, used for `field.workspace = true`.
+    package: Table,
+    /// `[workspace.metadata.slint-bundler]`.
+    bundler: Option<Table>,
+}
+
+impl WorkspaceDefaults {
+    pub fn read(workspace_root: &Path) -> Result<WorkspaceDefaults> {
+        let path = workspace_root.join(MANIFEST);
+        if !path.exists() {
+            return Ok(WorkspaceDefaults::default());
+        }
+        let manifest = read_manifest(&path)?;
+        let workspace = manifest.get("workspace").and_then(Value::as_table);
+        Ok(WorkspaceDefaults {
+            package: workspace
+                .and_then(|w| w.get("package"))
+                .and_then(Value::as_table)
+                .cloned()
+                .unwrap_or_default(),
+            bundler: workspace
+                .and_then(|w| w.get("metadata"))
+                .and_then(|m| m.get("slint-bundler"))
+                .and_then(Value::as_table)
+                .cloned(),
+        })
+    }
+}
+
+/// The legacy `slint-app.json` written by older versions of `slint-bundler new`.
+#[derive(Deserialize)]
+struct LegacyConfig {
+    app_id: Option<String>,
+    project_name: Option<String>,
+}
+
+impl Config {
+    /// Load the configuration of a workspace member, using the version cargo resolved for it.
+    pub fn for_package(project: &Project, package: &Package) -> Result<Config> {
+        let defaults = WorkspaceDefaults::read(&project.workspace_root)?;
+        let mut config = Self::load_from(package.manifest_dir(), &defaults)?;
+        if config.version_source == VersionSource::Cargo {
+            config.version = Version::parse(&package.version)
+                .with_context(|| format!("invalid version of package `{}`", package.name))?;
+        }
+        Ok(config)
+    }
+
+    /// Load the configuration of the package rooted at `dir`.
+    pub(crate) fn load_from(dir: &Path, defaults: &WorkspaceDefaults) -> Result<Config> {
+        let manifest_path = dir.join(MANIFEST);
+        let manifest = read_manifest(&manifest_path)?;
+        let package = manifest
+            .get("package")
+            .and_then(Value::as_table)
+            .with_context(|| format!("{} has no [package] table", manifest_path.display()))?;
+
+        // Resolve `field.workspace = true` against `[workspace.package]`.
+        let field = |key: &str| match package.get(key) {
+            Some(Value::Table(t)) if t.get("workspace").and_then(Value::as_bool) == Some(true) => {
+                defaults.package.get(key).cloned()
+            }
+            other => other.cloned(),
+        };
+        let string = |key: &str| field(key).and_then(|v| v.as_str().map(str::to_string));
+
+        let name = package
+            .get("name")
+            .and_then(Value::as_str)
+            .with_context(|| format!("{} has no package name", manifest_path.display()))?;
+
+        let own = package
+            .get("metadata")
+            .and_then(|m| m.get("slint-bundler"))
+            .and_then(Value::as_table);
+        let bundler = match (&defaults.bundler, own) {
+            (None, None) => read_legacy_config(&dir.join(LEGACY_CONFIG))?,
+            (base, own) => {
+                let mut merged = base.clone().unwrap_or_default();
+                if let Some(own) = own {
+                    merge(&mut merged, own);
+                }
+                Value::Table(merged).try_into().with_context(|| {
+                    format!("invalid [package.metadata.slint-bundler] in {}", manifest_path.display())
+                })?
+            }
+        };
+
+        if let Some(template) = &bundler.artifact_name {
+            check_artifact_name(template)
+                .with_context(|| format!("invalid artifact-name in {}", manifest_path.display()))?;
+        }
+        let resources = resources::expand(dir, &bundler.resources)
+            .with_context(|| format!("invalid resources in {}", manifest_path.display()))?;
+        let version = match bundler.version_source {
+            VersionSource::Cargo => Version::parse(&string("version").unwrap_or_else(|| "0.1.0".to_string())),
+            VersionSource::Git => Version::from_git(dir),
+        }
+        .with_context(|| format!("failed to determine the version for {}", manifest_path.display()))?;
+        let mut config = Self::resolve(
+            name.to_string(),
+            version,
+            string("description"),
+            string("homepage"),
+            string("license"),
+            field("authors").and_then(|v| v.try_into().ok()).unwrap_or_default(),
+            bundler,
+        );
+        config.resources = resources;
+        config
+            .linux
+            .scripts
+            .resolve(dir)
+            .with_context(|| format!("invalid linux.scripts in {}", manifest_path.display()))?;
+        config.license_file = match string("license-file") {
+            Some(file) => Some(dir.join(file)),
+            None => ["LICENSE", "LICENSE.md", "LICENSE.txt", "COPYING"]
+                .iter()
+                .map(|name| dir.join(name))
+                .find(|path| path.is_file()),
+        };
+        config.linux.changelog = match config.linux.changelog.take() {
+            Some(changelog) if !dir.join(&changelog).is_file() => {
+                bail!("linux.changelog {} not found", dir.join(changelog).display())
+            }
+            Some(changelog) => Some(dir.join(changelog)),
+            None => Some(dir.join("CHANGELOG.md")).filter(|path| path.is_file()),
+        };
+        Ok(config)
+    }
+
+    /// `windows.upgrade-code`, else a UUID v5 of the identifier, so a new release replaces
+    /// the installed one as long as the identifier stays the same.
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    pub fn upgrade_code(&self) -> String {
+        match &self.windows.upgrade_code {
+            Some(code) => code.clone(),
+            None => uuid_v5(&self.identifier),
+        }
+    }
+
+    fn resolve(
+        name: String,
+        version: Version,
+        description: Option<String>,
+        homepage: Option<String>,
+        license: Option<String>,
+        authors: Vec<String>,
+        bundler: BundlerConfig,
+    ) -> Config {
+        let display_name = bundler.display_name.unwrap_or_else(|| prettify_name(&name));
+        let publisher = bundler
+            .publisher
+            .or_else(|| authors.first().map(|a| strip_email(a)))
+            .unwrap_or_else(|| display_name.clone());
+        let description = bundler
+            .description
+            .or(description)
+            .unwrap_or_else(|| format!("{} packaged by slint-bundler", display_name));
+        let categories = if bundler.categories.is_empty() {
+            vec!["Utility".to_string()]
+        } else {
+            bundler.categories
+        };
+
+        Config {
+            identifier: bundler
+                .identifier
+                .unwrap_or_else(|| format!("com.slint.{}", name.replace('-', "_"))),
+            homepage: bundler.homepage.or(homepage),
+            license: bundler.license.or(license),
+            license_file: None,
+            name,
+            version,
+            version_source: bundler.version_source,
+            display_name,
+            publisher,
+            description,
+            authors,
+            categories,
+            targets: bundler.targets,
+            bins: bundler.bins,
+            artifact_name: bundler.artifact_name,
+            channel: bundler.channel.unwrap_or_else(|| "stable".to_string()),
+            linux: bundler.linux,
+            deb: bundler.deb,
+            rpm: bundler.rpm,
+            tar_zst: bundler.tar_zst,
+            tar_xz: bundler.tar_xz,
+            windows: bundler.windows,
+            android: bundler.android,
+            resources: Vec::new(),
+            hooks: bundler.hooks,
+        }
+    }
+}
+
+/// Name-based UUID (RFC 4122 version 5) of `name` in the DNS namespace, which fits
+/// reverse-DNS identifiers.
+fn uuid_v5(name: &str) -> String {
+    use sha1::{Digest, Sha1};
+    const NAMESPACE_DNS: [u8; 16] = [
+        0x6b, 0xa7, 0xb8, 0x10, 0x9d, 0xad, 0x11, 0xd1, 0x80, 0xb4, 0x00, 0xc0, 0x4f, 0xd4, 0x30, 0xc8,
+    ];
+    let hash = Sha1::new().chain_update(NAMESPACE_DNS).chain_update(name).finalize();
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&hash[..16]);
+    bytes[6] = (bytes[6] & 0x0f) | 0x50;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+/// Reject templates with unknown placeholders or that would not be a plain file name.
+fn check_artifact_name(template: &str) -> Result<()> {
+    if template.trim().is_empty() || template.contains(['/', '\\']) {
+        bail!("`{}` must be a non-empty file name", template);
+    }
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .with_context(|| format!("unclosed `{{` in `{}`", template))?;
+        let placeholder = &rest[start + 1..start + end];
+        if !ARTIFACT_NAME_PLACEHOLDERS.contains(&placeholder) {
+            bail!(
+                "unknown placeholder `{{{}}}` in `{}`, expected one of: {}",
+                placeholder,
+                template,
+                ARTIFACT_NAME_PLACEHOLDERS.iter().map(|p| format!("{{{}}}", p)).collect::<Vec<_>>().join(", ")
+            );
+        }
+        rest = &rest[start + end + 1..];
+    }
+    Ok(())
+}
+
+fn read_manifest(path: &Path) -> Result<Table> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    contents
+        .parse::<Table>()
+        .with_context(|| format!("failed to parse {}", path.display()))
+}
+
+/// Overlay `overlay` onto `base`, recursing into tables so a member only overrides the keys it sets.
+fn merge(base: &mut Table, overlay: &Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(key), value) {
+            (Some(Value::Table(base)), Value::Table(overlay)) => merge(base, overlay),
+            _ => {
+                base.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+fn read_legacy_config(path: &Path) -> Result<BundlerConfig> {
+    if !path.exists() {
+        return Ok(BundlerConfig::default());
+    }
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let legacy: LegacyConfig = serde_json::from_str(&contents)
+        .with_context(|| format!("failed to parse {}", path.display()))?;
+    Ok(BundlerConfig {
+        identifier: legacy.app_id,
+        display_name: legacy.project_name,
+        ..Default::default()
+    })
+}
+
+/// Converts a package name like "my-app" or "my_app" to "My App"
+pub fn prettify_name(name: &str) -> String {
+    name.split(['-', '_'])
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// "Jane Doe <jane@example.com>" -> "Jane Doe"
+fn strip_email(author: &str) -> String {
+    author.split('<').next().unwrap_or(author).trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A package directory with `manifest` as its `Cargo.toml` and an optional workspace root.
+    fn load(manifest: &str, workspace: Option<&str>) -> (tempfile::TempDir, Result<Config>) {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().join("member");
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join(MANIFEST), manifest).unwrap();
+        if let Some(workspace) = workspace {
+            fs::write(tmp.path().join(MANIFEST), workspace).unwrap();
+        }
+        let config = WorkspaceDefaults::read(tmp.path()).and_then(|defaults| Config::load_from(&dir, &defaults));
+        (tmp, config)
+    }
+
+    #[test]
+    fn defaults_from_the_package() {
+        let (_tmp, config) = load(
+            r#"[package]
+name = "my-app"
+version = "1.2.3"
+authors = ["Jane Doe <jane@example.com>"]
+"#,
+            None,
+        );
+        let config = config.unwrap();
+        assert_eq!(config.identifier, "com.slint.my_app");
+        assert_eq!(config.display_name, "My App");
+        assert_eq!(config.publisher, "Jane Doe");
+        assert_eq!(config.description, "My App packaged by slint-bundler");
+        assert_eq!(config.version.to_string(), "1.2.3");
+        assert_eq!(config.categories, ["Utility"]);
+        assert_eq!(config.upgrade_code(), "e54cfd77-0da9-576b-8084-a9beda0c776d");
+    }
+
+    #[test]
+    fn metadata_table() {
+        let (_tmp, config) = load(
+            r#"[package]
+name = "my-app"
+version = "1.0.0"
+description = "From Cargo"
+
+[package.metadata.slint-bundler]
+identifier = "com.example.app"
+display-name = "Example"
+publisher = "Example Inc."
+description = "From the metadata"
+
+[package.metadata.slint-bundler.windows]
+upgrade-code = "12345678-1234-1234-1234-123456789abc"
+"#,
+            None,
+        );
+        let config = config.unwrap();
+        assert_eq!(config.identifier, "com.example.app");
+        assert_eq!(config.display_name, "Example");
+        assert_eq!(config.publisher, "Example Inc.");
+        assert_eq!(config.description, "From the metadata");
+        assert_eq!(config.upgrade_code(), "12345678-1234-1234-1234-123456789abc");
+    }
+
+    #[test]
+    fn merges_workspace_defaults() {
+        let (_tmp, config) = load(
+            r#"[package]
+name = "member"
+version = "0.3.0"
+license.workspace = true
+
+[package.metadata.slint-bundler.linux]
+priority = "extra"
+"#,
+            Some(
+                r#"[workspace]
+members = ["member"]
+
+[workspace.package]
+license = "MIT OR Apache-2.0"
+
+[workspace.metadata.slint-bundler]
+publisher = "Workspace Inc."
+
+[workspace.metadata.slint-bundler.linux]
+section = "graphics"
+priority = "optional"
+"#,
+            ),
+        );
+        let config = config.unwrap();
+        assert_eq!(config.license.as_deref(), Some("MIT OR Apache-2.0"));
+        assert_eq!(config.publisher, "Workspace Inc.");
+        // The member's table only overrides the keys it sets
+        assert_eq!(config.linux.section.as_deref(), Some("graphics"));
+        assert_eq!(config.linux.priority, "extra");
+    }
+
+    #[test]
+    fn legacy_json_fallback() {
+        let tmp = tempfile::tempdir().unwrap();
+        fs::write(tmp.path().join(MANIFEST), "[package]\nname = \"legacy\"\nversion = \"0.1.0\"\n").unwrap();
+        fs::write(
+            tmp.path().join(LEGACY_CONFIG),
+            r#"{"app_id": "io.github.legacy", "project_name": "Legacy App", "version": "0.1.0", "package_name": "legacy"}"#,
+        )
+        .unwrap();
+        let config = Config::load_from(tmp.path(), &WorkspaceDefaults::default()).unwrap();
+        assert_eq!(config.identifier, "io.github.legacy");
+        assert_eq!(config.display_name, "Legacy App");
+
+        // A metadata table takes precedence over the legacy file
+        fs::write(
+            tmp.path().join(MANIFEST),
+            "[package]\nname = \"legacy\"\nversion = \"0.1.0\"\n\n[package.metadata.slint-bundler]\nidentifier = \"com.example.new\"\n",
+        )
+        .unwrap();
+        let config = Config::load_from(tmp.path(), &WorkspaceDefaults::default()).unwrap();
+        assert_eq!(config.identifier, "com.example.new");
+        assert_eq!(config.display_name, "Legacy");
+    }
+
+    #[test]
+    fn rejects_invalid_metadata() {
+        let (_tmp, config) = load(
+            "[package]\nname = \"app\"\nversion = \"1.0.0\"\n\n[package.metadata.slint-bundler]\nartifact-name = \"{name}-{os}\"\n",
+            None,
+        );
+        assert!(format!("{:#}", config.unwrap_err()).contains("unknown placeholder `{os}`"));
+        let (_tmp, config) = load("[package]\nversion = \"1.0.0\"\n", None);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn uuid_v5_matches_rfc_4122() {
+        assert_eq!(uuid_v5("com.example.app"), "280e37e9-46f9-5e07-8121-4dd6c340d0f6");
+    }
+}
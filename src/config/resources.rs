@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// One `[[package.metadata.slint-bundler.resources]]` entry as written by the user.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ResourceConfig {
+    /// Glob relative to the package directory, e.g. `translations/**/*.qm`.
+    pub src: String,
+    /// Logical destination directory, e.g. `$DATA/translations`.
+    pub dest: String,
+    /// Unix permission bits; defaults to 0755 below `$BIN` and 0644 elsewhere.
+    pub mode: Option<u32>,
+}
+
+/// Logical install location, mapped onto its own layout by every format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum InstallRoot {
+    /// `$BIN`: next to the executable.
+    Bin,
+    /// `$DATA`: read-only application data.
+    Data,
+    /// `$DOC`: documentation and licenses.
+    Doc,
+    /// `$ETC`: system-wide configuration, kept across upgrades where the format supports it.
+    Etc,
+}
+
+impl InstallRoot {
+    /// Name as written in `dest`.
+    pub fn var(self) -> &'static str {
+        match self {
+            InstallRoot::Bin => "$BIN",
+            InstallRoot::Data => "$DATA",
+            InstallRoot::Doc => "$DOC",
+            InstallRoot::Etc => "$ETC",
+        }
+    }
+}
+
+/// A single file matched by a resource pattern.
+#[derive(Clone, Debug)]
+pub struct Resource {
+    pub source: PathBuf,
+    pub root: InstallRoot,
+    /// Install path below `root`, including the file name.
+    pub path: PathBuf,
+    pub mode: u32,
+}
+
+/// Expand the resource patterns of the package rooted at `dir` into the files they match.
+/// Matches keep their path below the pattern's first wildcard, so `i18n/**/*.qm` installs
+/// `i18n/de/app.qm` as `<dest>/de/app.qm`.
+pub fn expand(dir: &Path, resources: &[ResourceConfig]) -> Result<Vec<Resource>> {
+    let mut expanded = Vec::new();
+    for resource in resources {
+        if expand_into(dir, resource, &mut expanded)? == 0 {
+            bail!("resource pattern `{}` matched no files", resource.src);
+        }
+    }
+    Ok(expanded)
+}
+
+/// `resources` plus the files below `bin/`, `data/`, `doc/` and `etc/` of `dir`, installed to
+/// `$BIN`, `$DATA`, `$DOC` and `$ETC`. Used for files a pre-bundle hook stages.
+pub fn collect_staged(dir: &Path, resources: &[Resource]) -> Result<Vec<Resource>> {
+    let mut collected = resources.to_vec();
+    for root in [InstallRoot::Bin, InstallRoot::Data, InstallRoot::Doc, InstallRoot::Etc] {
+        let sub = root.var().trim_start_matches('$').to_lowercase();
+        let resource = ResourceConfig {
+            src: format!("{}/**/*", sub),
+            dest: root.var().to_string(),
+            mode: None,
+        };
+        expand_into(dir, &resource, &mut collected)?;
+    }
+    Ok(collected)
+}
+
+/// Append the files matching `resource` to `expanded` and return how many there were.
+fn expand_into(dir: &Path, resource: &ResourceConfig, expanded: &mut Vec<Resource>) -> Result<usize> {
+    let (root, dest) = parse_dest(&resource.dest)?;
+    let mut seen: HashMap<(InstallRoot, PathBuf), PathBuf> = expanded
+        .iter()
+        .map(|r| ((r.root, r.path.clone()), r.source.clone()))
+        .collect();
+    let pattern = format!("{}/{}", glob::Pattern::escape(&dir.to_string_lossy()), resource.src);
+    let base = dir.join(literal_prefix(&resource.src));
+    let matches = glob::glob(&pattern)
+        .with_context(|| format!("invalid resource pattern `{}`", resource.src))?;
+    let before = expanded.len();
+    for source in matches {
+        let source = source.with_context(|| format!("failed to expand `{}`", resource.src))?;
+        if !source.is_file() {
+            continue;
+        }
+        let path = dest.join(source.strip_prefix(&base).unwrap_or(&source));
+        if let Some(other) = seen.insert((root, path.clone()), source.clone()) {
+            bail!(
+                "{} and {} both install to {}/{}",
+                other.display(),
+                source.display(),
+                root.var(),
+                path.display()
+            );
+        }
+        expanded.push(Resource {
+            source,
+            root,
+            path,
+            mode: resource.mode.unwrap_or(match root {
+                InstallRoot::Bin => 0o755,
+                _ => 0o644,
+            }),
+        });
+    }
+    Ok(expanded.len() - before)
+}
+
+/// `$DATA/translations` -> (`Data`, `translations`)
+fn parse_dest(dest: &str) -> Result<(InstallRoot, PathBuf)> {
+    let (var, rest) = dest.split_once('/').unwrap_or((dest, ""));
+    let root = match var {
+        "$BIN" => InstallRoot::Bin,
+        "$DATA" => InstallRoot::Data,
+        "$DOC" => InstallRoot::Doc,
+        "$ETC" => InstallRoot::Etc,
+        _ => bail!("resource destination `{}` must start with $BIN, $DATA, $DOC or $ETC", dest),
+    };
+    let rest = Path::new(rest);
+    if rest.components().any(|c| !matches!(c, Component::Normal(_) | Component::CurDir)) {
+        bail!("resource destination `{}` must stay below {}", dest, var);
+    }
+    Ok((root, rest.to_path_buf()))
+}
+
+/// The directories of `pattern` before its first wildcard; for a plain path, its parent.
+fn literal_prefix(pattern: &str) -> PathBuf {
+    let components: Vec<&str> = pattern.split('/').collect();
+    let literal = components
+        .iter()
+        .position(|c| c.contains(['*', '?', '[']))
+        .unwrap_or(components.len() - 1);
+    components[..literal].iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use super::*;
+
+    fn files(dir: &Path, paths: &[&str]) {
+        for path in paths {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, b"").unwrap();
+        }
+    }
+
+    fn resource(src: &str, dest: &str) -> ResourceConfig {
+        ResourceConfig {
+            src: src.to_string(),
+            dest: dest.to_string(),
+            mode: None,
+        }
+    }
+
+    fn installed(resources: &[Resource]) -> Vec<(InstallRoot, String, u32)> {
+        let mut installed: Vec<_> = resources
+            .iter()
+            .map(|r| (r.root, r.path.to_string_lossy().replace('\\', "/"), r.mode))
+            .collect();
+        installed.sort_by(|a, b| a.1.cmp(&b.1));
+        installed
+    }
+
+    #[test]
+    fn globs_keep_the_path_below_the_wildcard() {
+        let tmp = tempfile::tempdir().unwrap();
+        files(tmp.path(), &["i18n/de/app.qm", "i18n/fr/app.qm", "i18n/README", "LICENSE", "tools/run.sh"]);
+        let resources = [
+            resource("i18n/**/*.qm", "$DATA/translations"),
+            resource("LICENSE", "$DOC"),
+            ResourceConfig {
+                mode: Some(0o700),
+                ..resource("tools/*", "$BIN")
+            },
+        ];
+        assert_eq!(
+            installed(&expand(tmp.path(), &resources).unwrap()),
+            [
+                (InstallRoot::Doc, "LICENSE".to_string(), 0o644),
+                (InstallRoot::Bin, "run.sh".to_string(), 0o700),
+                (InstallRoot::Data, "translations/de/app.qm".to_string(), 0o644),
+                (InstallRoot::Data, "translations/fr/app.qm".to_string(), 0o644),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_bad_resources() {
+        let tmp = tempfile::tempdir().unwrap();
+        files(tmp.path(), &["a/app.conf", "b/app.conf"]);
+        assert!(expand(tmp.path(), &[resource("*.missing", "$DATA")]).is_err());
+        assert!(expand(tmp.path(), &[resource("a/*", "/usr/share")]).is_err());
+        assert!(expand(tmp.path(), &[resource("a/*", "$ETC/../..")]).is_err());
+        // Two files installed to the same path
+        let err = expand(tmp.path(), &[resource("a/*", "$ETC"), resource("b/*", "$ETC")]).unwrap_err();
+        assert!(err.to_string().contains("$ETC/app.conf"), "{}", err);
+    }
+
+    #[test]
+    fn destinations() {
+        assert_eq!(parse_dest("$BIN").unwrap(), (InstallRoot::Bin, PathBuf::new()));
+        assert_eq!(parse_dest("$DATA/a/b").unwrap(), (InstallRoot::Data, PathBuf::from("a/b")));
+        assert_eq!(parse_dest("$DOC/./x").unwrap().0, InstallRoot::Doc);
+        assert_eq!(parse_dest("$ETC/app").unwrap(), (InstallRoot::Etc, PathBuf::from("app")));
+        assert!(parse_dest("$LIB/app").is_err());
+        assert!(parse_dest("$DATA/../etc").is_err());
+    }
+
+    #[test]
+    fn collects_staged_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let package = tmp.path().join("package");
+        let staged = tmp.path().join("staged");
+        files(&package, &["icon.png"]);
+        files(&staged, &["bin/helper", "data/themes/dark.css", "doc/NOTICE", "etc/app.conf", "other/ignored"]);
+        let resources = expand(&package, &[resource("icon.png", "$DATA")]).unwrap();
+        assert_eq!(
+            installed(&collect_staged(&staged, &resources).unwrap()),
+            [
+                (InstallRoot::Doc, "NOTICE".to_string(), 0o644),
+                (InstallRoot::Etc, "app.conf".to_string(), 0o644),
+                (InstallRoot::Bin, "helper".to_string(), 0o755),
+                (InstallRoot::Data, "icon.png".to_string(), 0o644),
+                (InstallRoot::Data, "themes/dark.css".to_string(), 0o644),
+            ]
+        );
+        // Staged files may not replace configured resources
+        files(&staged, &["data/icon.png"]);
+        assert!(collect_staged(&staged, &resources).is_err());
+        // An empty staging directory adds nothing
+        let empty = tmp.path().join("empty");
+        fs::create_dir(&empty).unwrap();
+        assert_eq!(collect_staged(&empty, &resources).unwrap().len(), 1);
+    }
+}
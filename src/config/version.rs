@@ -0,0 +1,264 @@
+use std::fmt;
+use std::path::Path;
+use std::process::Command;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Where the package version comes from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VersionSource {
+    /// `version` in `Cargo.toml`.
+    #[default]
+    Cargo,
+    /// `git describe --tags`; commits after the tag make a snapshot version.
+    Git,
+}
+
+/// A semver package version, mapped onto the version rules of every format.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Version {
+    pub semver: semver::Version,
+    /// Commits since the last tag and the abbreviated commit hash, for untagged commits.
+    pub snapshot: Option<(u32, String)>,
+}
+
+impl Version {
+    pub fn parse(version: &str) -> Result<Version> {
+        let semver = semver::Version::parse(version.trim())
+            .with_context(|| format!("`{}` is not a semver version", version))?;
+        Ok(Version { semver, snapshot: None })
+    }
+
+    /// The version of the latest tag reachable from `HEAD` in `dir`; tags may start with `v`.
+    pub fn from_git(dir: &Path) -> Result<Version> {
+        let output = Command::new("git")
+            .args(["describe", "--tags", "--long", "--abbrev=7"])
+            .current_dir(dir)
+            .output()
+            .context("failed to run git describe")?;
+        if !output.status.success() {
+            bail!(
+                "git describe found no tag to take the version from: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Self::from_describe(String::from_utf8_lossy(&output.stdout).trim())
+    }
+
+    /// `v1.2.0-beta.1-3-gabc1234` -> `1.2.0-beta.1`, 3 commits after the tag at abc1234.
+    fn from_describe(describe: &str) -> Result<Version> {
+        let mut parts = describe.rsplitn(3, '-');
+        let (Some(sha), Some(commits), Some(tag)) = (parts.next(), parts.next(), parts.next()) else {
+            bail!("unexpected git describe output `{}`", describe);
+        };
+        let commits: u32 = commits
+            .parse()
+            .with_context(|| format!("unexpected git describe output `{}`", describe))?;
+        let mut version = Self::parse(tag.strip_prefix('v').unwrap_or(tag))
+            .with_context(|| format!("tag `{}` is not a version", tag))?;
+        if commits > 0 {
+            version.snapshot = Some((commits, sha.trim_start_matches('g').to_string()));
+        }
+        Ok(version)
+    }
+
+    fn numbers(&self) -> (u64, u64, u64) {
+        (self.semver.major, self.semver.minor, self.semver.patch)
+    }
+
+    /// Pre-release identifiers, rejecting `-` that the Linux formats use as separator.
+    fn pre(&self, format: &str) -> Result<Option<&str>> {
+        let pre = self.semver.pre.as_str();
+        if pre.contains('-') {
+            bail!("{} versions cannot contain `-`, as in the pre-release `{}`", format, pre);
+        }
+        Ok((!pre.is_empty()).then_some(pre))
+    }
+
+    /// Fail for formats without pre-releases or snapshots, which would install as the
+    /// plain release and break upgrades.
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    fn require_release(&self, format: &str) -> Result<()> {
+        if !self.semver.pre.is_empty() {
+            bail!("{} cannot represent the pre-release version {}", format, self);
+        }
+        if self.snapshot.is_some() {
+            bail!("{} cannot represent the snapshot version {}, tag the commit", format, self);
+        }
+        Ok(())
+    }
+
+    /// `[epoch:]1.2.0~beta.1`; `~` sorts pre-releases before the release and snapshots
+    /// get `+git<commits>.g<hash>`.
+    pub fn deb(&self, epoch: Option<u32>) -> Result<String> {
+        let (major, minor, patch) = self.numbers();
+        let mut version = epoch.map(|e| format!("{}:", e)).unwrap_or_default();
+        version.push_str(&format!("{}.{}.{}", major, minor, patch));
+        if let Some(pre) = self.pre("deb")? {
+            version.push_str(&format!("~{}", pre));
+        }
+        if let Some((commits, sha)) = &self.snapshot {
+            version.push_str(&format!("+git{}.g{}", commits, sha));
+        }
+        Ok(version)
+    }
+
+    /// Version and release: `1.2.0` and `0.1.beta.1` for a pre-release, `1` otherwise,
+    /// with `.git<commits>.g<hash>` appended for snapshots.
+    pub fn rpm(&self) -> Result<(String, String)> {
+        let (major, minor, patch) = self.numbers();
+        let mut release = match self.pre("rpm")? {
+            Some(pre) => format!("0.1.{}", pre),
+            None => "1".to_string(),
+        };
+        if let Some((commits, sha)) = &self.snapshot {
+            release.push_str(&format!(".git{}.g{}", commits, sha));
+        }
+        Ok((format!("{}.{}.{}", major, minor, patch), release))
+    }
+
+    /// pkgver: `1.2.0beta.1`, which vercmp sorts before `1.2.0`, and `.r<commits>.g<hash>`
+    /// for snapshots.
+    pub fn pacman(&self) -> Result<String> {
+        let (major, minor, patch) = self.numbers();
+        let mut version = format!("{}.{}.{}", major, minor, patch);
+        if let Some(pre) = self.pre("pacman")? {
+            if !pre.starts_with(|c: char| c.is_ascii_alphabetic()) {
+                bail!("pacman versions need pre-releases starting with a letter, got `{}`", pre);
+            }
+            version.push_str(pre);
+        }
+        if let Some((commits, sha)) = &self.snapshot {
+            version.push_str(&format!(".r{}.g{}", commits, sha));
+        }
+        Ok(version)
+    }
+
+    /// `major.minor.build` with at most 255, 255 and 65535.
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    pub fn msi(&self) -> Result<String> {
+        self.require_release("MSI")?;
+        let (major, minor, patch) = self.numbers();
+        if major > 255 || minor > 255 || patch > 65535 {
+            bail!("MSI versions are limited to 255.255.65535, got {}", self);
+        }
+        Ok(format!("{}.{}.{}", major, minor, patch))
+    }
+
+    /// Four parts of at most 65535 each; the last is reserved for the Store and stays 0.
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    pub fn msix(&self) -> Result<String> {
+        self.require_release("MSIX")?;
+        let (major, minor, patch) = self.numbers();
+        if major > 65535 || minor > 65535 || patch > 65535 {
+            bail!("MSIX version parts are limited to 65535, got {}", self);
+        }
+        Ok(format!("{}.{}.{}.0", major, minor, patch))
+    }
+
+    /// `versionCode` derived as `major * 1000000 + minor * 1000 + patch`.
+    pub fn android_code(&self) -> Result<u32> {
+        if !self.semver.pre.is_empty() || self.snapshot.is_some() {
+            bail!("cannot derive an Android versionCode from {}, set android.version-code", self);
+        }
+        let (major, minor, patch) = self.numbers();
+        // Google Play accepts up to 2100000000
+        if major >= 2100 || minor >= 1000 || patch >= 1000 {
+            bail!("cannot derive an Android versionCode from {}, set android.version-code", self);
+        }
+        Ok((major * 1_000_000 + minor * 1_000 + patch) as u32)
+    }
+}
+
+/// The semver version; snapshots add `git.<commits>.g<hash>` build metadata.
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.semver)?;
+        if let Some((commits, sha)) = &self.snapshot {
+            let separator = if self.semver.build.is_empty() { '+' } else { '.' };
+            write!(f, "{}git.{}.g{}", separator, commits, sha)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(v: &str) -> Version {
+        Version::parse(v).unwrap()
+    }
+
+    fn snapshot(v: &str) -> Version {
+        Version { snapshot: Some((3, "abc1234".to_string())), ..version(v) }
+    }
+
+    #[test]
+    fn deb() {
+        assert_eq!(version("1.2.0").deb(None).unwrap(), "1.2.0");
+        assert_eq!(version("1.2.0").deb(Some(2)).unwrap(), "2:1.2.0");
+        assert_eq!(version("1.2.0-beta.1").deb(None).unwrap(), "1.2.0~beta.1");
+        assert_eq!(snapshot("1.2.0").deb(None).unwrap(), "1.2.0+git3.gabc1234");
+        assert!(version("1.2.0-rc-1").deb(None).is_err());
+    }
+
+    #[test]
+    fn rpm() {
+        assert_eq!(version("1.2.0").rpm().unwrap(), ("1.2.0".to_string(), "1".to_string()));
+        assert_eq!(version("1.2.0-beta.1").rpm().unwrap(), ("1.2.0".to_string(), "0.1.beta.1".to_string()));
+        assert_eq!(snapshot("1.2.0").rpm().unwrap(), ("1.2.0".to_string(), "1.git3.gabc1234".to_string()));
+        assert!(version("1.2.0-rc-1").rpm().is_err());
+    }
+
+    #[test]
+    fn pacman() {
+        assert_eq!(version("1.2.0").pacman().unwrap(), "1.2.0");
+        assert_eq!(version("1.2.0-beta.1").pacman().unwrap(), "1.2.0beta.1");
+        assert_eq!(snapshot("1.2.0").pacman().unwrap(), "1.2.0.r3.gabc1234");
+        assert!(version("1.2.0-1").pacman().is_err());
+    }
+
+    #[test]
+    fn msi() {
+        assert_eq!(version("1.2.3").msi().unwrap(), "1.2.3");
+        assert_eq!(version("255.255.65535").msi().unwrap(), "255.255.65535");
+        assert!(version("256.0.0").msi().is_err());
+        assert!(version("1.256.0").msi().is_err());
+        assert!(version("1.0.65536").msi().is_err());
+        assert!(version("1.2.0-beta.1").msi().is_err());
+        assert!(snapshot("1.2.0").msi().is_err());
+    }
+
+    #[test]
+    fn msix() {
+        assert_eq!(version("1.2.3").msix().unwrap(), "1.2.3.0");
+        assert!(version("65536.0.0").msix().is_err());
+        assert!(version("1.2.0-beta.1").msix().is_err());
+        assert!(snapshot("1.2.0").msix().is_err());
+    }
+
+    #[test]
+    fn android_code() {
+        assert_eq!(version("1.2.3").android_code().unwrap(), 1_002_003);
+        assert_eq!(version("2099.999.999").android_code().unwrap(), 2_099_999_999);
+        assert!(version("2100.0.0").android_code().is_err());
+        assert!(version("1.1000.0").android_code().is_err());
+        assert!(version("1.2.0-beta.1").android_code().is_err());
+        assert!(snapshot("1.2.0").android_code().is_err());
+    }
+
+    #[test]
+    fn from_describe() {
+        let tagged = Version::from_describe("v1.2.0-0-gabc1234").unwrap();
+        assert_eq!(tagged, version("1.2.0"));
+        let after = Version::from_describe("v1.2.0-beta.1-3-gabc1234").unwrap();
+        assert_eq!(after.semver, version("1.2.0-beta.1").semver);
+        assert_eq!(after.snapshot, Some((3, "abc1234".to_string())));
+        assert_eq!(after.to_string(), "1.2.0-beta.1+git.3.gabc1234");
+        assert_eq!(Version::from_describe("1.2.0-0-gabc1234").unwrap(), version("1.2.0"));
+        assert!(Version::from_describe("v1.2.0").is_err());
+        assert!(Version::from_describe("release-3-gabc1234").is_err());
+    }
+}
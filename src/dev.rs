@@ -1,22 +1,31 @@
 use notify::{RecommendedWatcher, RecursiveMode, Watcher, EventKind};
+use std::path::Path;
 use std::process::{Command, Child};
 use std::sync::mpsc::channel;
 use std::time::Duration;
+use anyhow::{anyhow, Context as _};
+use crate::error::{Error, Result};
 
-pub fn handle_dev() {
-    let (tx, rx) = channel();
-    let mut watcher = RecommendedWatcher::new(tx, notify::Config::default()).expect("Failed to create watcher");
-    let src_path = std::path::Path::new("src").canonicalize().expect("Failed to canonicalize src path");
-    let ui_path = std::path::Path::new("ui").canonicalize().expect("Failed to canonicalize ui path");
-    let cargo_path = std::path::Path::new("Cargo.toml").canonicalize().expect("Failed to canonicalize Cargo manifest Path");
-    println!("Watching: {}", src_path.display());
-    watcher.watch(&src_path, RecursiveMode::Recursive).expect("Failed to watch src");
-
-    println!("Watching: {}", ui_path.display());
-    watcher.watch(&ui_path, RecursiveMode::Recursive).expect("Failed to watch ui");
+/// Watch the project's `src`, `ui` and `Cargo.toml`; a missing one means we are not in a
+/// Slint project directory.
+pub fn watch(watcher: &mut RecommendedWatcher) -> Result<()> {
+    for path in ["src", "ui", "Cargo.toml"] {
+        let path = Path::new(path).canonicalize().map_err(|e| {
+            Error::Config(anyhow!("{} not found, run this from the project directory: {}", path, e))
+        })?;
+        println!("Watching: {}", path.display());
+        watcher
+            .watch(&path, RecursiveMode::Recursive)
+            .with_context(|| format!("failed to watch {}", path.display()))?;
+    }
+    Ok(())
+}
 
-    println!("Watching {}", cargo_path.display());
-    watcher.watch(&cargo_path, RecursiveMode::Recursive).expect("Failed to watch Cargo.toml");
+pub fn handle_dev() -> Result<()> {
+    let (tx, rx) = channel();
+    let mut watcher = RecommendedWatcher::new(tx, notify::Config::default())
+        .context("failed to create file watcher")?;
+    watch(&mut watcher)?;
 
     // Compile and run at the start
     println!("Initial build (dev profile)...");
@@ -34,29 +43,24 @@ pub fn handle_dev() {
 
     loop {
         // println!("Waiting for file changes...");
-        match rx.recv_timeout(Duration::from_millis(500)) {
-            Ok(event) => {
-                if let Ok(event) = event {
-                    if matches!(event.kind, EventKind::Modify(_)) {
-                        if let Some(mut c) = child.take() {
-                            let _ = c.kill();
-                            let _ = c.wait();
-                        }
-                        println!("Rebuilding (dev profile)...");
-                        let status = Command::new("cargo").arg("run").spawn();
-                        match status {
-                            Ok(c) => {
-                                println!("Running app...");
-                                child = Some(c);
-                            }
-                            Err(e) => {
-                                eprintln!("Failed to run cargo: {}", e);
-                            }
-                        }
+        if let Ok(Ok(event)) = rx.recv_timeout(Duration::from_millis(500)) {
+            if matches!(event.kind, EventKind::Modify(_)) {
+                if let Some(mut c) = child.take() {
+                    let _ = c.kill();
+                    let _ = c.wait();
+                }
+                println!("Rebuilding (dev profile)...");
+                let status = Command::new("cargo").arg("run").spawn();
+                match status {
+                    Ok(c) => {
+                        println!("Running app...");
+                        child = Some(c);
+                    }
+                    Err(e) => {
+                        eprintln!("Failed to run cargo: {}", e);
                     }
                 }
             }
-            Err(_) => {}
         }
     }
 }
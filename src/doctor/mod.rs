@@ -1,8 +1,9 @@
-use anyhow::Result;
+use anyhow::{Context as _, Result};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::env;
 use whoami::username;
+use crate::events::{self, status, Event};
 
 
 #[derive(Clone, Debug)]
@@ -82,7 +83,24 @@ impl Default for Doctor {
     }
 }
 impl Doctor {
-    fn fix(&self, search_dirs: Vec<String>) {
+    /// Emit a `tool-checked` event per check, for `--message-format json`.
+    fn emit(&self) {
+        for group in &self.groups {
+            for check in &group.checks {
+                let path = check.path().ok();
+                let version = path.as_ref().and_then(|_| check.version().ok().flatten());
+                events::emit(&Event::ToolChecked {
+                    group: group.name,
+                    name: check.name(),
+                    found: path.is_some(),
+                    version,
+                    path: path.as_deref(),
+                });
+            }
+        }
+    }
+
+    fn fix(&self, search_dirs: Vec<String>) -> Result<()> {
         let mut found_dirs: Vec<PathBuf> = Vec::new();
 
         for group in &self.groups {
@@ -101,11 +119,7 @@ impl Doctor {
                     if !dir_path.exists() {
                         continue;
                     }
-                    //let message = for
-                    let executable = exe_name.to_string();
-                    let diret = dir_path.to_str().expect("Failure");
-                    
-                    println!("Found {executable} in {diret}");
+                    status!("Found {} in {}", exe_name, dir_path.display());
                     if !found_dirs.contains(&dir_path.to_path_buf()) {
                         found_dirs.push(dir_path.to_path_buf());
                     }
@@ -117,15 +131,15 @@ impl Doctor {
     
         let mut paths = env::split_paths(&env::var_os("PATH").unwrap_or_default()).collect::<Vec<_>>();
         for dir in found_dirs.iter() {
-            if !paths.contains(&dir) {
-                println!("🔧 Adding '{}' to PATH", dir.display());
+            if !paths.contains(dir) {
+                status!("🔧 Adding '{}' to PATH", dir.display());
                 paths.push(dir.to_path_buf());
             }
         }
 
-        // ✅ Fix: unwrap the Result<OsString, JoinPathsError>
-        let new_path = env::join_paths(paths).expect("Failed to join PATH entries");
+        let new_path = env::join_paths(paths).context("failed to join PATH entries")?;
         env::set_var("PATH", new_path);
+        Ok(())
     }
 }
 
@@ -223,9 +237,13 @@ impl Check {
     }
 }
 
-pub fn doctor(fix: bool) {
+pub fn doctor(fix: bool) -> crate::error::Result<()> {
     let doctor = Doctor::default();
-    print!("{doctor}");
+    if events::is_json() {
+        doctor.emit();
+    } else {
+        print!("{doctor}");
+    }
     let user = username();
     #[cfg(target_os = "windows")]
     let search_dirs = vec![
@@ -276,7 +294,8 @@ pub fn doctor(fix: bool) {
         "/usr/local/bin".to_string(),
     ];
     if fix {
-        println!("Attempting to find tools and add to path");
-        Doctor::fix(&doctor, search_dirs);
+        status!("Attempting to find tools and add to path");
+        Doctor::fix(&doctor, search_dirs)?;
     }
+    Ok(())
 }
\ No newline at end of file
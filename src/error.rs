@@ -0,0 +1,64 @@
+use std::io;
+
+/// Errors that end a `slint-bundler` invocation, each mapped to its own exit code
+/// so CI scripts can tell a missing tool from a broken build.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Bad or missing command line arguments.
+    #[error("{0}")]
+    Usage(String),
+    /// `Cargo.toml`, `cargo metadata` or the bundler configuration could not be read.
+    #[error("{0:#}")]
+    Config(anyhow::Error),
+    /// An external program a bundler needs is not installed.
+    #[error("{tool} not found in PATH")]
+    MissingTool { tool: String },
+    /// `cargo build` failed.
+    #[error("{0}")]
+    Build(String),
+    /// A bundler failed for another reason.
+    #[error("failed to create {bundle} bundle: {error:#}")]
+    Bundle { bundle: String, error: anyhow::Error },
+    /// `verify` found artifacts that do not match the manifest, or `inspect` a broken package.
+    #[error("{0}")]
+    Verify(String),
+    /// A configured hook command could not be started or failed.
+    #[error("{hook} hook `{command}` failed: {reason}")]
+    Hook { hook: String, command: String, reason: String },
+    /// Several builds or bundles of one `build` invocation failed.
+    #[error("{} failures, see the summary above", .0.len())]
+    Bundles(Vec<Error>),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("{0:#}")]
+    Other(#[from] anyhow::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub fn missing_tool(tool: &str) -> Error {
+        Error::MissingTool { tool: tool.to_string() }
+    }
+
+    /// Process exit code: 1 internal, 2 usage, 3 configuration, 4 missing tool,
+    /// 5 cargo build, 6 bundling, 7 verification or inspection, 8 hook.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Usage(_) => 2,
+            Error::Config(_) => 3,
+            Error::MissingTool { .. } => 4,
+            Error::Build(_) => 5,
+            Error::Bundle { .. } => 6,
+            Error::Verify(_) => 7,
+            Error::Hook { .. } => 8,
+            // Keep a specific code when every failure had the same cause
+            Error::Bundles(failures) => {
+                let mut codes = failures.iter().map(Error::exit_code);
+                let first = codes.next().unwrap_or(6);
+                if codes.all(|c| c == first) { first } else { 6 }
+            }
+            Error::Io(_) | Error::Other(_) => 1,
+        }
+    }
+}
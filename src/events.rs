@@ -0,0 +1,155 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::OnceLock;
+use serde::Serialize;
+
+/// `--message-format` of `build`, `android build` and `doctor`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum MessageFormat {
+    /// Progress text for people
+    #[default]
+    Human,
+    /// Newline-delimited JSON events on stdout, progress text on stderr
+    Json,
+}
+
+static FORMAT: OnceLock<MessageFormat> = OnceLock::new();
+
+pub fn set_format(format: MessageFormat) {
+    let _ = FORMAT.set(format);
+}
+
+pub fn is_json() -> bool {
+    FORMAT.get() == Some(&MessageFormat::Json)
+}
+
+/// One line of `--message-format json` output, tagged by `reason` like cargo's messages.
+#[derive(Debug, Serialize)]
+#[serde(tag = "reason", rename_all = "kebab-case")]
+pub enum Event<'a> {
+    BuildStarted {
+        packages: Vec<&'a str>,
+        targets: Vec<&'a str>,
+        profile: &'a str,
+    },
+    CargoFinished {
+        target: &'a str,
+        success: bool,
+    },
+    BundleStarted {
+        package: &'a str,
+        format: &'a str,
+        target: &'a str,
+    },
+    /// A bundle was skipped because its inputs match the cache.
+    CacheHit {
+        package: &'a str,
+        format: &'a str,
+        target: &'a str,
+    },
+    ArtifactCreated {
+        package: &'a str,
+        format: &'a str,
+        arch: &'a str,
+        target: &'a str,
+        path: &'a Path,
+        size: u64,
+        sha256: String,
+    },
+    BuildFinished {
+        success: bool,
+        artifacts: usize,
+        failures: usize,
+    },
+    ToolChecked {
+        group: &'a str,
+        name: &'a str,
+        found: bool,
+        version: Option<String>,
+        path: Option<&'a Path>,
+    },
+    Warning {
+        message: String,
+    },
+    Error {
+        message: String,
+        exit_code: i32,
+    },
+}
+
+/// Write `event` to stdout when JSON output is enabled.
+pub fn emit(event: &Event) {
+    if !is_json() {
+        return;
+    }
+    let mut out = io::stdout().lock();
+    if serde_json::to_writer(&mut out, event).is_ok() {
+        let _ = writeln!(out);
+    }
+}
+
+/// Emit `artifact-created` for a file written by a bundler.
+pub fn artifact_created(package: &str, format: &str, arch: &str, target: &str, path: &Path) {
+    if !is_json() {
+        return;
+    }
+    emit(&Event::ArtifactCreated {
+        package,
+        format,
+        arch,
+        target,
+        path,
+        size: std::fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+        sha256: sha256::try_digest(path).unwrap_or_default(),
+    });
+}
+
+/// Print progress text; it goes to stderr in JSON mode so stdout stays machine readable.
+pub fn print_status(args: fmt::Arguments) {
+    if is_json() {
+        eprintln!("{}", args);
+    } else {
+        println!("{}", args);
+    }
+}
+
+pub fn warning(message: impl fmt::Display) {
+    if is_json() {
+        emit(&Event::Warning { message: message.to_string() });
+    } else {
+        eprintln!("Warning: {}", message);
+    }
+}
+
+pub fn error(error: &crate::error::Error) {
+    if is_json() {
+        emit(&Event::Error {
+            message: error.to_string(),
+            exit_code: error.exit_code(),
+        });
+    } else {
+        eprintln!("Error: {}", error);
+    }
+}
+
+/// Stdout for child processes: inherited, or redirected to stderr in JSON mode.
+pub fn child_stdout() -> Stdio {
+    if is_json() {
+        io::stderr().into()
+    } else {
+        Stdio::inherit()
+    }
+}
+
+/// `println!` replacement for progress output, see [`print_status`].
+macro_rules! status {
+    () => {
+        $crate::events::print_status(format_args!(""))
+    };
+    ($($arg:tt)*) => {
+        $crate::events::print_status(format_args!($($arg)*))
+    };
+}
+pub(crate) use status;
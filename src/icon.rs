@@ -77,7 +77,7 @@ pub fn generate_android_icons() -> Result<(), Box<dyn std::error::Error>> {
     ];
 
     let base = Path::new(OUTPUT_PATH);
-    std::fs::create_dir_all(&base)?;
+    std::fs::create_dir_all(base)?;
 
     for (mipmap_dir, size) in ANDROID_SIZES {
         let dir_path = base.join(mipmap_dir);
@@ -0,0 +1,98 @@
+use crate::bundle::Arch;
+
+/// Architecture of an ELF or PE executable, `None` for anything else and for CPUs
+/// without a package architecture.
+pub fn arch(data: &[u8]) -> Option<Arch> {
+    if data.starts_with(b"\x7fELF") {
+        // e_machine follows the 16 byte ident and the 2 byte e_type
+        let machine = data.get(18..20)?;
+        let big_endian = *data.get(5)? == 2;
+        let machine = if big_endian {
+            u16::from_be_bytes([machine[0], machine[1]])
+        } else {
+            u16::from_le_bytes([machine[0], machine[1]])
+        };
+        return match machine {
+            0x3e => Some(Arch::X86_64),
+            0xb7 => Some(Arch::Aarch64),
+            0x28 => {
+                // EF_ARM_ABI_FLOAT_HARD in the 32-bit e_flags
+                let flags: [u8; 4] = data.get(36..40)?.try_into().ok()?;
+                let flags = if big_endian { u32::from_be_bytes(flags) } else { u32::from_le_bytes(flags) };
+                Some(if flags & 0x400 != 0 { Arch::Armhf } else { Arch::Armel })
+            }
+            0x03 => Some(Arch::I686),
+            0xf3 => Some(Arch::Riscv64),
+            _ => None,
+        };
+    }
+    if data.starts_with(b"MZ") {
+        let offset = u32::from_le_bytes(data.get(0x3c..0x40)?.try_into().ok()?) as usize;
+        if data.get(offset..offset + 4)? != b"PE\0\0" {
+            return None;
+        }
+        let machine = data.get(offset + 4..offset + 6)?;
+        return match u16::from_le_bytes([machine[0], machine[1]]) {
+            0x8664 => Some(Arch::X86_64),
+            0xaa64 => Some(Arch::Aarch64),
+            0x01c4 => Some(Arch::Armhf),
+            0x014c => Some(Arch::I686),
+            _ => None,
+        };
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn elf(class: u8, data: u8, machine: u16, flags: u32) -> Vec<u8> {
+        let mut header = vec![0u8; 64];
+        header[..6].copy_from_slice(&[0x7f, b'E', b'L', b'F', class, data]);
+        let (machine, flags) = if data == 2 {
+            (machine.to_be_bytes(), flags.to_be_bytes())
+        } else {
+            (machine.to_le_bytes(), flags.to_le_bytes())
+        };
+        header[18..20].copy_from_slice(&machine);
+        header[36..40].copy_from_slice(&flags);
+        header
+    }
+
+    fn pe(machine: u16) -> Vec<u8> {
+        let mut image = vec![0u8; 0x48];
+        image[..2].copy_from_slice(b"MZ");
+        image[0x3c..0x40].copy_from_slice(&0x40u32.to_le_bytes());
+        image[0x40..0x44].copy_from_slice(b"PE\0\0");
+        image[0x44..0x46].copy_from_slice(&machine.to_le_bytes());
+        image
+    }
+
+    #[test]
+    fn elf_machines() {
+        assert_eq!(arch(&elf(2, 1, 0x3e, 0)), Some(Arch::X86_64));
+        assert_eq!(arch(&elf(2, 1, 0xb7, 0)), Some(Arch::Aarch64));
+        assert_eq!(arch(&elf(2, 1, 0xf3, 0)), Some(Arch::Riscv64));
+        assert_eq!(arch(&elf(1, 1, 0x03, 0)), Some(Arch::I686));
+        // The float ABI of 32-bit ARM is in e_flags
+        assert_eq!(arch(&elf(1, 1, 0x28, 0x0500_0400)), Some(Arch::Armhf));
+        assert_eq!(arch(&elf(1, 1, 0x28, 0x0500_0200)), Some(Arch::Armel));
+        assert_eq!(arch(&elf(1, 2, 0x28, 0x0500_0400)), Some(Arch::Armhf));
+        // PowerPC has no package architecture
+        assert_eq!(arch(&elf(2, 2, 0x15, 0)), None);
+        assert_eq!(arch(b"\x7fELF"), None);
+    }
+
+    #[test]
+    fn pe_machines() {
+        assert_eq!(arch(&pe(0x8664)), Some(Arch::X86_64));
+        assert_eq!(arch(&pe(0xaa64)), Some(Arch::Aarch64));
+        assert_eq!(arch(&pe(0x014c)), Some(Arch::I686));
+        assert_eq!(arch(&pe(0x0200)), None);
+        let mut not_pe = pe(0x8664);
+        not_pe[0x40..0x44].copy_from_slice(b"NE\0\0");
+        assert_eq!(arch(&not_pe), None);
+        assert_eq!(arch(b"#!/bin/sh\n"), None);
+    }
+}
@@ -0,0 +1,463 @@
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::process::Command;
+use anyhow::{bail, Context as _, Result};
+use md5::{Digest, Md5};
+use regex::Regex;
+use crate::bundle::Arch;
+use crate::inspect::{binary, file_name_arch, FileEntry, Report};
+
+/// Fields `dpkg` refuses to install a package without.
+const DEB_FIELDS: &[&str] = &["Package", "Version", "Architecture", "Maintainer", "Description"];
+const RPM_FIELDS: &[&str] = &["Name", "Version", "Release", "Arch", "Summary", "License"];
+const PKGINFO_FIELDS: &[&str] = &["pkgname", "pkgver", "arch"];
+const MAINTAINER_SCRIPTS: &[&str] = &["preinst", "postinst", "prerm", "postrm"];
+
+/// A file or symlink read from a tar archive.
+struct TarEntry {
+    /// Without a leading `./`.
+    path: String,
+    mode: u32,
+    link: Option<String>,
+    data: Vec<u8>,
+}
+
+/// An ar archive of `debian-binary`, `control.tar.*` and `data.tar.*`.
+pub fn deb(path: &Path, file_name: &str) -> Result<Report> {
+    let mut report = Report::new("deb package");
+    let mut archive = ar::Archive::new(fs::File::open(path)?);
+    let mut members = Vec::new();
+    while let Some(entry) = archive.next_entry() {
+        let mut entry = entry.context("invalid ar archive")?;
+        let name = String::from_utf8_lossy(entry.header().identifier()).to_string();
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data)?;
+        members.push((name, data));
+    }
+
+    match members.first() {
+        Some((name, data)) if name == "debian-binary" => {
+            if data != b"2.0\n" {
+                report.problem(format!("debian-binary contains {:?}, expected \"2.0\\n\"", String::from_utf8_lossy(data)));
+            }
+        }
+        _ => report.problem("the first archive member is not debian-binary"),
+    }
+    let mut md5sums = None;
+    let mut conffiles = None;
+    match members.iter().find(|(name, _)| name.starts_with("control.tar")) {
+        Some((name, data)) => {
+            let entries = read_tar(decompress(name, data)?).with_context(|| format!("invalid {}", name))?;
+            let text = |path: &str| {
+                entries
+                    .iter()
+                    .find(|e| e.path == path)
+                    .map(|e| String::from_utf8_lossy(&e.data).to_string())
+            };
+            md5sums = text("md5sums");
+            conffiles = text("conffiles");
+            for script in entries.iter().filter(|e| MAINTAINER_SCRIPTS.contains(&e.path.as_str())) {
+                if script.mode & 0o111 == 0 {
+                    report.problem(format!("maintainer script {} is not executable", script.path));
+                }
+            }
+            match entries.iter().find(|e| e.path == "control") {
+                Some(control) => {
+                    report.metadata = parse_control(&String::from_utf8_lossy(&control.data));
+                    report.require_fields("control", DEB_FIELDS);
+                }
+                None => report.problem(format!("{} has no control file", name)),
+            }
+        }
+        None => report.problem("no control.tar member"),
+    }
+    match members.iter().find(|(name, _)| name.starts_with("data.tar")) {
+        Some((name, data)) => {
+            let entries = read_tar(decompress(name, data)?).with_context(|| format!("invalid {}", name))?;
+            match &md5sums {
+                Some(md5sums) => check_md5sums(&mut report, md5sums, &entries),
+                None => report.problem("no md5sums in the control archive"),
+            }
+            for conffile in conffiles.iter().flat_map(|c| c.lines()).filter(|l| !l.trim().is_empty()) {
+                let path = conffile.trim().trim_start_matches('/');
+                if !entries.iter().any(|e| e.path == path && e.link.is_none()) {
+                    report.problem(format!("conffiles lists {} which is not a file in the package", conffile.trim()));
+                }
+            }
+            add_entries(&mut report, entries);
+        }
+        None => report.problem("no data.tar member"),
+    }
+
+    if let Some(arch) = report.field("Architecture").map(str::to_string) {
+        report.check_arch("Architecture", &arch, Arch::deb);
+        check_file_name(&mut report, file_name, ".deb", &arch, Arch::deb);
+    }
+    Ok(report)
+}
+
+/// Header fields and file list of an rpm; the payload is unpacked to find executables.
+pub fn rpm(path: &Path, file_name: &str) -> Result<Report> {
+    let mut report = Report::new("rpm package");
+    let package = rpm::Package::open(path).context("invalid rpm")?;
+    let header = &package.metadata;
+    for (key, value) in [
+        ("Name", header.get_name()),
+        ("Version", header.get_version()),
+        ("Release", header.get_release()),
+        ("Arch", header.get_arch()),
+        ("Summary", header.get_summary()),
+        ("License", header.get_license()),
+        ("URL", header.get_url()),
+    ] {
+        if let Ok(value) = value {
+            report.metadata.push((key.to_string(), value.to_string()));
+        }
+    }
+    let compression = header.get_payload_compressor().context("invalid payload compressor")?;
+    report.metadata.push(("Payload".to_string(), format!("{:?}", compression).to_lowercase()));
+    report.require_fields("header", RPM_FIELDS);
+    if let Err(e) = package.verify_digests() {
+        report.problem(format!("digest check failed: {}", e));
+    }
+
+    for entry in header.get_file_entries().context("invalid file list")? {
+        let path = entry.path.to_string_lossy().trim_start_matches('/').to_string();
+        let mode = u32::from(entry.mode);
+        match entry.mode {
+            rpm::FileMode::Dir { .. } => {}
+            rpm::FileMode::SymbolicLink { .. } => report.files.push(FileEntry {
+                path,
+                mode: Some(mode),
+                size: 0,
+                link: Some(entry.linkto.clone()),
+            }),
+            _ => report.files.push(FileEntry {
+                path,
+                mode: Some(mode),
+                size: entry.size as u64,
+                link: None,
+            }),
+        }
+    }
+
+    // The payload is a newc cpio archive
+    let payload = package.content.as_slice();
+    let mut reader: Box<dyn Read + '_> = match compression {
+        rpm::CompressionType::None => Box::new(payload),
+        rpm::CompressionType::Gzip => Box::new(flate2::read::GzDecoder::new(payload)),
+        rpm::CompressionType::Xz => Box::new(xz2::read::XzDecoder::new(payload)),
+        rpm::CompressionType::Zstd => Box::new(zstd::Decoder::new(payload)?),
+        rpm::CompressionType::Bzip2 => Box::new(bzip2::read::BzDecoder::new(payload)),
+    };
+    loop {
+        let mut entry = cpio::NewcReader::new(reader).context("invalid cpio payload")?;
+        if entry.entry().is_trailer() {
+            break;
+        }
+        let path = entry.entry().name().trim_start_matches("./").trim_start_matches('/').to_string();
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data).context("truncated cpio payload")?;
+        if let Some(arch) = binary::arch(&data) {
+            report.binaries.push((path, arch));
+        }
+        reader = entry.finish()?;
+    }
+
+    if let Some(arch) = report.field("Arch").map(str::to_string) {
+        report.check_arch("Arch", &arch, Arch::rpm);
+        check_file_name(&mut report, file_name, ".rpm", &arch, Arch::rpm);
+    }
+    Ok(report)
+}
+
+/// A `.tar.zst` or `.tar.xz`; pacman packages carry their metadata in `.PKGINFO`.
+pub fn tarball(path: &Path, file_name: &str) -> Result<Report> {
+    let data = fs::read(path)?;
+    let entries = read_tar(decompress(file_name, &data)?).context("invalid tar archive")?;
+    let extension = if file_name.ends_with(".tar.zst") { ".tar.zst" } else { ".tar.xz" };
+    let pkginfo = entries.iter().find(|e| e.path == ".PKGINFO").map(|e| parse_pkginfo(&e.data));
+    let mut report = Report::new(if pkginfo.is_some() { "pacman package" } else { "tarball" });
+    add_entries(&mut report, entries);
+
+    match pkginfo {
+        Some(fields) => {
+            report.metadata = fields;
+            report.require_fields(".PKGINFO", PKGINFO_FIELDS);
+            if let Some(arch) = report.field("arch").map(str::to_string) {
+                report.check_arch("arch", &arch, Arch::pacman);
+                check_file_name(&mut report, file_name, extension, &arch, Arch::pacman);
+            }
+        }
+        None => {
+            if let Some(arch) = file_name_arch(file_name, extension, Arch::pacman) {
+                report.check_arch("the file name's architecture", arch, Arch::pacman);
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// The AppDir `.tar.gz` written when appimagetool is not available.
+pub fn appdir(path: &Path) -> Result<Report> {
+    let data = fs::read(path)?;
+    let mut report = Report::new("AppDir archive");
+    add_entries(&mut report, read_tar(decompress(".tar.gz", &data)?).context("invalid tar archive")?);
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    if let Some(arch) = file_name_arch(&file_name, ".tar.gz", Arch::appimage) {
+        report.check_arch("the file name's architecture", arch, Arch::appimage);
+    }
+    check_appdir(&mut report);
+    Ok(report)
+}
+
+/// A type 2 AppImage: an ELF runtime followed by a squashfs image of the AppDir.
+pub fn appimage(path: &Path, file_name: &str) -> Result<Report> {
+    let data = fs::read(path)?;
+    let mut report = Report::new("AppImage");
+    if !data.starts_with(b"\x7fELF") {
+        report.problem("not an ELF executable");
+        return Ok(report);
+    }
+    if data.get(8..11) != Some(b"AI\x02".as_slice()) {
+        report.problem("missing the AppImage type 2 magic bytes");
+    }
+    if let Some(arch) = binary::arch(&data) {
+        report.metadata.push(("Runtime".to_string(), arch.name().to_string()));
+        if let Some(declared) = file_name_arch(file_name, ".AppImage", Arch::appimage) {
+            if arch.appimage() != declared {
+                report.problem(format!("the runtime is built for {} but the file name says {}", arch.name(), declared));
+            }
+        }
+    }
+    let Some(offset) = elf_end(&data).filter(|&o| data.get(o..o + 4) == Some(b"hsqs".as_slice())) else {
+        report.problem("no squashfs image after the runtime");
+        return Ok(report);
+    };
+    report.metadata.push(("Squashfs offset".to_string(), offset.to_string()));
+
+    // Listing the squashfs needs unsquashfs; without it only the runtime is checked
+    let output = match Command::new("unsquashfs").arg("-o").arg(offset.to_string()).arg("-lln").arg(path).output() {
+        Ok(output) if output.status.success() => output,
+        Ok(output) => bail!("unsquashfs failed: {}", String::from_utf8_lossy(&output.stderr).trim()),
+        Err(_) => {
+            report.metadata.push(("Files".to_string(), "not listed, unsquashfs not found".to_string()));
+            return Ok(report);
+        }
+    };
+    let line = Regex::new(r"^([-dl][-rwxsStT]{9})\s+\S+\s+(\d+)\s+\S+\s+\S+\s+squashfs-root/(.+)$").unwrap();
+    for captures in String::from_utf8_lossy(&output.stdout).lines().filter_map(|l| line.captures(l)) {
+        let kind = &captures[1][..1];
+        let size = captures[2].parse().unwrap_or(0);
+        let (path, link) = match captures[3].split_once(" -> ") {
+            Some((path, target)) if kind == "l" => (path.to_string(), Some(target.to_string())),
+            _ => (captures[3].to_string(), None),
+        };
+        if kind != "d" {
+            report.files.push(FileEntry {
+                path,
+                mode: Some(parse_mode(&captures[1][1..])),
+                size,
+                link,
+            });
+        }
+    }
+    check_appdir(&mut report);
+    Ok(report)
+}
+
+/// An AppDir needs the AppRun entry point and a desktop file at its root.
+fn check_appdir(report: &mut Report) {
+    let root: Vec<&str> = report.files.iter().map(|f| f.path.as_str()).filter(|p| !p.contains('/')).collect();
+    let has_apprun = root.contains(&"AppRun");
+    let has_desktop = root.iter().any(|p| p.ends_with(".desktop"));
+    if !has_apprun {
+        report.problem("no AppRun at the root of the AppDir");
+    }
+    if !has_desktop {
+        report.problem("no .desktop file at the root of the AppDir");
+    }
+}
+
+/// Compare every `md5sums` line with the file it names in the data archive.
+fn check_md5sums(report: &mut Report, md5sums: &str, entries: &[TarEntry]) {
+    for line in md5sums.lines().filter(|l| !l.trim().is_empty()) {
+        let Some((sum, path)) = line.split_once("  ") else {
+            report.problem(format!("malformed md5sums line {:?}", line));
+            continue;
+        };
+        match entries.iter().find(|e| e.path == path && e.link.is_none()) {
+            Some(entry) => {
+                let actual: String = Md5::digest(&entry.data).iter().map(|b| format!("{:02x}", b)).collect();
+                if actual != sum {
+                    report.problem(format!("md5sums lists {} for {} but its checksum is {}", sum, path, actual));
+                }
+            }
+            None => report.problem(format!("md5sums lists {} which is not in the package", path)),
+        }
+    }
+}
+
+/// Complain when the architecture in the file name differs from the declared one.
+fn check_file_name(report: &mut Report, file_name: &str, extension: &str, declared: &str, name: fn(Arch) -> &'static str) {
+    if let Some(arch) = file_name_arch(file_name, extension, name) {
+        if arch != declared {
+            report.problem(format!("the file name says {} but the package is {}", arch, declared));
+        }
+    }
+}
+
+/// Offset just past the section header table, where an AppImage's squashfs starts.
+fn elf_end(data: &[u8]) -> Option<usize> {
+    let le = *data.get(5)? != 2;
+    let read = |range: std::ops::Range<usize>| -> Option<u64> {
+        let bytes = data.get(range)?;
+        let mut value = 0u64;
+        for (i, byte) in bytes.iter().enumerate() {
+            let shift = if le { i } else { bytes.len() - 1 - i };
+            value |= (*byte as u64) << (8 * shift);
+        }
+        Some(value)
+    };
+    let (shoff, shentsize, shnum) = match data.get(4)? {
+        2 => (read(0x28..0x30)?, read(0x3a..0x3c)?, read(0x3c..0x3e)?),
+        _ => (read(0x20..0x24)?, read(0x2e..0x30)?, read(0x30..0x32)?),
+    };
+    usize::try_from(shoff + shentsize * shnum).ok()
+}
+
+/// `rwxr-xr-x` -> `0o755`
+fn parse_mode(permissions: &str) -> u32 {
+    permissions
+        .chars()
+        .enumerate()
+        .filter(|(_, c)| *c != '-' && *c != 'S' && *c != 'T')
+        .fold(0, |mode, (i, _)| mode | (1 << (8 - i)))
+}
+
+fn decompress<'a>(name: &str, data: &'a [u8]) -> Result<Box<dyn Read + 'a>> {
+    Ok(match name.rsplit('.').next() {
+        Some("gz") => Box::new(flate2::read::GzDecoder::new(data)),
+        Some("xz") => Box::new(xz2::read::XzDecoder::new(data)),
+        Some("zst") => Box::new(zstd::Decoder::new(data)?),
+        Some("tar") => Box::new(data),
+        _ => bail!("unsupported compression of {}", name),
+    })
+}
+
+/// Files and symlinks of a tar archive; directories are skipped.
+fn read_tar(reader: impl Read) -> Result<Vec<TarEntry>> {
+    let mut archive = tar::Archive::new(reader);
+    let mut entries = Vec::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.to_string_lossy().trim_start_matches("./").to_string();
+        let mode = entry.header().mode()?;
+        let (link, data) = match entry.header().entry_type() {
+            tar::EntryType::Directory => continue,
+            tar::EntryType::Symlink => (entry.link_name()?.map(|l| l.to_string_lossy().to_string()), Vec::new()),
+            _ => {
+                let mut data = Vec::new();
+                entry.read_to_end(&mut data)?;
+                (None, data)
+            }
+        };
+        entries.push(TarEntry { path, mode, link, data });
+    }
+    Ok(entries)
+}
+
+fn add_entries(report: &mut Report, entries: Vec<TarEntry>) {
+    for entry in entries {
+        match entry.link {
+            Some(target) => report.files.push(FileEntry {
+                path: entry.path,
+                mode: Some(entry.mode),
+                size: 0,
+                link: Some(target),
+            }),
+            None => report.add_file(entry.path, Some(entry.mode), &entry.data),
+        }
+    }
+}
+
+/// `Key: value` lines of a Debian control file; indented lines continue the previous field.
+fn parse_control(text: &str) -> Vec<(String, String)> {
+    let mut fields: Vec<(String, String)> = Vec::new();
+    for line in text.lines() {
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = fields.last_mut() {
+                value.push('\n');
+                value.push_str(line.trim());
+            }
+        } else if let Some((key, value)) = line.split_once(':') {
+            fields.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    fields
+}
+
+/// `key = value` lines of a pacman `.PKGINFO`.
+fn parse_pkginfo(data: &[u8]) -> Vec<(String, String)> {
+    String::from_utf8_lossy(data)
+        .lines()
+        .filter(|l| !l.starts_with('#'))
+        .filter_map(|l| l.split_once(" = "))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn control_fields() {
+        let control = "Package: app\nVersion: 1.0.0\nDescription: An app\n A longer text\n .\n More\n";
+        assert_eq!(
+            parse_control(control),
+            [
+                ("Package".to_string(), "app".to_string()),
+                ("Version".to_string(), "1.0.0".to_string()),
+                ("Description".to_string(), "An app\nA longer text\n.\nMore".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn pkginfo_fields() {
+        let pkginfo = b"# Generated by makepkg\npkgname = app\npkgdesc = Says a = b\narch = x86_64\n";
+        assert_eq!(
+            parse_pkginfo(pkginfo),
+            [
+                ("pkgname".to_string(), "app".to_string()),
+                ("pkgdesc".to_string(), "Says a = b".to_string()),
+                ("arch".to_string(), "x86_64".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn modes() {
+        assert_eq!(parse_mode("rwxr-xr-x"), 0o755);
+        assert_eq!(parse_mode("rw-r--r--"), 0o644);
+        assert_eq!(parse_mode("rwsr-xr-T"), 0o754);
+        assert_eq!(parse_mode("---------"), 0);
+    }
+
+    #[test]
+    fn elf_ends_after_the_section_headers() {
+        // Linkers put the section header table last
+        let exe = fs::read(std::env::current_exe().unwrap()).unwrap();
+        assert_eq!(elf_end(&exe), Some(exe.len()));
+
+        let mut header = vec![0u8; 64];
+        header[..6].copy_from_slice(b"\x7fELF\x02\x01");
+        header[0x28..0x30].copy_from_slice(&0x1000u64.to_le_bytes());
+        header[0x3a..0x3c].copy_from_slice(&64u16.to_le_bytes());
+        header[0x3c..0x3e].copy_from_slice(&3u16.to_le_bytes());
+        assert_eq!(elf_end(&header), Some(0x1000 + 3 * 64));
+        assert_eq!(elf_end(&header[..32]), None);
+    }
+}
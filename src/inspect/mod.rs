@@ -0,0 +1,206 @@
+use std::path::Path;
+use crate::bundle::Arch;
+use crate::error::{Error, Result};
+use crate::events::status;
+
+mod binary;
+#[cfg(target_os = "linux")]
+mod linux;
+mod msix;
+
+/// What `inspect` read back from a package.
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Human readable format, e.g. `deb package`.
+    pub format: &'static str,
+    /// Metadata fields in the order the package stores them.
+    pub metadata: Vec<(String, String)>,
+    /// Files and symlinks; directories are implied.
+    pub files: Vec<FileEntry>,
+    /// Executables found in the package with the architecture they were built for.
+    pub binaries: Vec<(String, Arch)>,
+    /// Structural problems; any of them makes `inspect` fail.
+    pub problems: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct FileEntry {
+    pub path: String,
+    /// Permission bits; `None` for formats without Unix modes.
+    pub mode: Option<u32>,
+    pub size: u64,
+    /// Target of a symbolic link.
+    pub link: Option<String>,
+}
+
+impl Report {
+    fn new(format: &'static str) -> Report {
+        Report {
+            format,
+            ..Default::default()
+        }
+    }
+
+    pub fn field(&self, key: &str) -> Option<&str> {
+        self.metadata.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    pub fn problem(&mut self, problem: impl Into<String>) {
+        self.problems.push(problem.into());
+    }
+
+    /// Record a file, and its architecture if `data` is an executable.
+    pub fn add_file(&mut self, path: String, mode: Option<u32>, data: &[u8]) {
+        if let Some(arch) = binary::arch(data) {
+            self.binaries.push((path.clone(), arch));
+        }
+        self.files.push(FileEntry {
+            path,
+            mode,
+            size: data.len() as u64,
+            link: None,
+        });
+    }
+
+    /// Flag executables built for another architecture than `declared`, in the format's
+    /// naming given by `name`.
+    pub fn check_arch(&mut self, what: &str, declared: &str, name: fn(Arch) -> &'static str) {
+        let wrong: Vec<String> = self
+            .binaries
+            .iter()
+            .filter(|(_, arch)| name(*arch) != declared)
+            .map(|(path, arch)| format!("{} is built for {} but {} is {}", path, arch.name(), what, declared))
+            .collect();
+        self.problems.extend(wrong);
+    }
+
+    /// Require every field of `keys` to be present and non-empty.
+    pub fn require_fields(&mut self, what: &str, keys: &[&str]) {
+        for key in keys {
+            if self.field(key).is_none_or(|v| v.trim().is_empty()) {
+                self.problem(format!("{} has no {} field", what, key));
+            }
+        }
+    }
+}
+
+/// The architecture ending a file name like `app_1.0.0_amd64.deb` or
+/// `app-1.0.0-1.x86_64.rpm`, given its `extension` and the format's naming of architectures.
+/// Custom `artifact-name` templates may leave it out.
+pub fn file_name_arch(file_name: &str, extension: &str, name: fn(Arch) -> &'static str) -> Option<&'static str> {
+    let stem = file_name.strip_suffix(extension)?;
+    // pacman packages end in `.pkg.tar.zst`
+    let stem = stem.strip_suffix(".pkg").unwrap_or(stem);
+    Arch::ALL
+        .into_iter()
+        .map(name)
+        .find(|arch| stem.strip_suffix(arch).is_some_and(|rest| rest.ends_with(['_', '-', '.'])))
+}
+
+/// Read back the package at `path`, print what it contains and fail on structural problems.
+pub fn inspect(path: &Path) -> Result<()> {
+    let report = read(path)?;
+    print_report(path, &report);
+    if !report.problems.is_empty() {
+        return Err(Error::Verify(format!(
+            "{} has {} problem(s)",
+            path.display(),
+            report.problems.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Read back the package at `path`, choosing the reader by its extension.
+pub fn read(path: &Path) -> Result<Report> {
+    if !path.is_file() {
+        return Err(Error::Usage(format!("{} is not a file", path.display())));
+    }
+    let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let result = if file_name.ends_with(".msix") {
+        msix::inspect(path)
+    } else {
+        inspect_linux(path, &file_name)?
+    };
+    result.map_err(|e| Error::Verify(format!("failed to read {}: {:#}", path.display(), e)))
+}
+
+#[cfg(target_os = "linux")]
+fn inspect_linux(path: &Path, file_name: &str) -> Result<anyhow::Result<Report>> {
+    Ok(if file_name.ends_with(".deb") {
+        linux::deb(path, file_name)
+    } else if file_name.ends_with(".rpm") {
+        linux::rpm(path, file_name)
+    } else if file_name.ends_with(".tar.zst") || file_name.ends_with(".tar.xz") {
+        linux::tarball(path, file_name)
+    } else if file_name.ends_with(".tar.gz") {
+        linux::appdir(path)
+    } else if file_name.ends_with(".AppImage") {
+        linux::appimage(path, file_name)
+    } else {
+        return Err(unsupported(file_name));
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn inspect_linux(_path: &Path, file_name: &str) -> Result<anyhow::Result<Report>> {
+    const LINUX: &[&str] = &[".deb", ".rpm", ".tar.zst", ".tar.xz", ".tar.gz", ".AppImage"];
+    if LINUX.iter().any(|ext| file_name.ends_with(ext)) {
+        return Err(Error::Usage(format!("inspecting {} requires a Linux host", file_name)));
+    }
+    Err(unsupported(file_name))
+}
+
+fn unsupported(file_name: &str) -> Error {
+    Error::Usage(format!(
+        "don't know how to inspect {}, expected a .deb, .rpm, .tar.zst, .tar.xz, .AppImage, AppDir .tar.gz or .msix file",
+        file_name
+    ))
+}
+
+fn print_report(path: &Path, report: &Report) {
+    status!("{}: {}", path.display(), report.format);
+    let width = report.metadata.iter().map(|(k, _)| k.len()).max().unwrap_or(0);
+    for (key, value) in &report.metadata {
+        status!("  {:width$}  {}", key, value, width = width);
+    }
+    status!("Files:");
+    for file in &report.files {
+        let mode = file.mode.map(|m| format!("{:04o}", m & 0o7777)).unwrap_or_else(|| "-".to_string());
+        match &file.link {
+            Some(target) => status!("  {:>4}  {:>10}  {} -> {}", mode, file.size, file.path, target),
+            None => status!("  {:>4}  {:>10}  {}", mode, file.size, file.path),
+        }
+    }
+    if !report.binaries.is_empty() {
+        status!("Executables:");
+        for (path, arch) in &report.binaries {
+            status!("  {}  {}", arch.name(), path);
+        }
+    }
+    if report.problems.is_empty() {
+        status!("No problems found");
+    } else {
+        status!("Problems:");
+        for problem in &report.problems {
+            status!("  {}", problem);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_name_archs() {
+        assert_eq!(file_name_arch("app_1.0.0_amd64.deb", ".deb", Arch::deb), Some("amd64"));
+        assert_eq!(file_name_arch("app-1.0.0-1.armv7hl.rpm", ".rpm", Arch::rpm), Some("armv7hl"));
+        assert_eq!(file_name_arch("app-1.0.0-1-armv7h.pkg.tar.zst", ".tar.zst", Arch::pacman), Some("armv7h"));
+        assert_eq!(file_name_arch("app-1.0.0-1-arm.pkg.tar.xz", ".tar.xz", Arch::pacman), Some("arm"));
+        // Custom artifact names may leave the architecture out
+        assert_eq!(file_name_arch("app.deb", ".deb", Arch::deb), None);
+        assert_eq!(file_name_arch("app_1.0.0_amd64.deb", ".rpm", Arch::rpm), None);
+        assert_eq!(file_name_arch("appx86_64.rpm", ".rpm", Arch::rpm), None);
+    }
+}
@@ -0,0 +1,108 @@
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use anyhow::{Context as _, Result};
+use crate::bundle::Arch;
+use crate::inspect::Report;
+
+/// Parts every MSIX package must contain.
+const REQUIRED_PARTS: &[&str] = &["AppxManifest.xml", "AppxBlockMap.xml", "[Content_Types].xml"];
+
+/// A zip with the AppxManifest, block map and content types next to the payload.
+pub fn inspect(path: &Path) -> Result<Report> {
+    let mut report = Report::new("msix package");
+    let mut archive = zip::ZipArchive::new(fs::File::open(path)?).context("invalid zip archive")?;
+    let mut manifest = None;
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i).context("invalid zip entry")?;
+        if file.is_dir() {
+            continue;
+        }
+        let name = file.name().to_string();
+        let mut data = Vec::new();
+        file.read_to_end(&mut data).with_context(|| format!("failed to read {}", name))?;
+        if name == "AppxManifest.xml" {
+            manifest = Some(String::from_utf8_lossy(&data).to_string());
+        }
+        report.add_file(name, file.unix_mode(), &data);
+    }
+
+    for part in REQUIRED_PARTS {
+        if !report.files.iter().any(|f| f.path == *part) {
+            report.problem(format!("no {}", part));
+        }
+    }
+    let Some(manifest) = manifest else {
+        return Ok(report);
+    };
+    for (key, tag, attribute) in [
+        ("Name", "Identity", "Name"),
+        ("Version", "Identity", "Version"),
+        ("Publisher", "Identity", "Publisher"),
+        ("ProcessorArchitecture", "Identity", "ProcessorArchitecture"),
+        ("DisplayName", "DisplayName", ""),
+        ("Executable", "Application", "Executable"),
+    ] {
+        if let Some(value) = xml_value(&manifest, tag, attribute) {
+            report.metadata.push((key.to_string(), value));
+        }
+    }
+    report.require_fields("AppxManifest.xml", &["Name", "Version", "Publisher", "Executable"]);
+
+    if let Some(executable) = report.field("Executable").map(|e| e.replace('\\', "/")) {
+        if !report.files.iter().any(|f| f.path == executable) {
+            report.problem(format!("the Application executable {} is not in the package", executable));
+        }
+    }
+    if let Some(arch) = report.field("ProcessorArchitecture").map(str::to_string) {
+        if arch != "neutral" {
+            report.check_arch("ProcessorArchitecture", &arch, Arch::msix);
+        }
+    }
+    Ok(report)
+}
+
+/// Value of `attribute` on the first `<tag>`, or its text content when `attribute` is empty.
+/// Good enough for the flat manifests the msix crate writes.
+fn xml_value(xml: &str, tag: &str, attribute: &str) -> Option<String> {
+    let open = format!("<{}", tag);
+    let start = xml
+        .match_indices(&open)
+        .map(|(i, _)| i + open.len())
+        .find(|&i| xml[i..].starts_with([' ', '>', '/', '\n', '\r', '\t']))?;
+    let end = start + xml[start..].find('>')?;
+    if attribute.is_empty() {
+        let text = &xml[end + 1..];
+        return Some(text[..text.find('<')?].trim().to_string());
+    }
+    let element = &xml[start..end];
+    let key = format!("{}=\"", attribute);
+    let (at, _) = element
+        .match_indices(&key)
+        .find(|(i, _)| element[..*i].ends_with(char::is_whitespace))?;
+    let value = &element[at + key.len()..];
+    Some(value[..value.find('"')?].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xml_values() {
+        let xml = r#"<Package>
+  <Identity Name="com.example.app" Version="1.0.0.0" ProcessorArchitecture="x64" />
+  <Properties>
+    <DisplayName> Example </DisplayName>
+    <PublisherDisplayName>Example Inc</PublisherDisplayName>
+  </Properties>
+</Package>"#;
+        assert_eq!(xml_value(xml, "Identity", "Name").as_deref(), Some("com.example.app"));
+        assert_eq!(xml_value(xml, "Identity", "ProcessorArchitecture").as_deref(), Some("x64"));
+        assert_eq!(xml_value(xml, "DisplayName", "").as_deref(), Some("Example"));
+        // Neither a prefix of another tag nor a suffix of another attribute
+        assert_eq!(xml_value(xml, "Publisher", ""), None);
+        assert_eq!(xml_value(xml, "Identity", "Architecture"), None);
+        assert_eq!(xml_value(xml, "Logo", ""), None);
+    }
+}
@@ -2,79 +2,58 @@ mod bundle;
 mod dev;
 mod icon;
 mod doctor;
+mod inspect;
 mod android;
 mod new;
-mod help;
+mod config;
+mod project;
+mod error;
+mod cli;
+mod events;
 pub mod utils;
-use crate::help::generate_help_message;
-
-use std::env;
-
-const USAGE: &str = "Usage: slint-bundler <command> [options]
-
-Commands:
-  build     Build and bundle the project
-            Options: --bundles <bundle-types>
-  dev       Run the project in dev mode (auto-recompile on change)
-  icon      Generate icons from PNG input
-            Options: --input <png-file>
-  doctor    Check environment setup
-            Options: --fix
-  android   Build Android package
-  help      Print this message or help for specific command
-
-Examples:
-  slint-bundler build --bundles deb,msi
-  slint-bundler icon --input icon.png
-  slint-bundler help build";
+use std::fs;
+use std::io;
+use clap::{CommandFactory, Parser};
+use crate::cli::{AndroidCommand, Cli, Command};
+use crate::error::Result;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let cli = Cli::parse();
+    match &cli.command {
+        Command::Build(args) => events::set_format(args.message_format),
+        Command::Doctor { message_format, .. }
+        | Command::Android { command: AndroidCommand::Build { message_format } } => {
+            events::set_format(*message_format)
+        }
+        _ => {}
+    }
+    if let Err(e) = run(cli) {
+        events::error(&e);
+        std::process::exit(e.exit_code());
+    }
+}
 
-    match args[1].as_str() {
-        "new" => new::handle_new(),
-        "build" => {
-            let bundles = parse_bundles(&args);
-            bundle::handle_build(bundles);
+fn run(cli: Cli) -> Result<()> {
+    match cli.command {
+        Command::New => new::handle_new()?,
+        Command::Build(args) => bundle::handle_build(args.into())?,
+        Command::Dev => dev::handle_dev()?,
+        Command::Icon { input } => {
+            icon::generate_pngs(&input.to_string_lossy())
+                .map_err(|e| anyhow::anyhow!("failed to generate pngs: {}", e))?;
         },
-        "dev" => dev::handle_dev(),
-        "icon" => icon::generate_pngs(&args[2]).expect("Failed to generate pngs"),
-        "doctor" => {
-            let fix = args.contains(&"--fix".to_string());
-            doctor::doctor(fix);
+        Command::Doctor { fix, .. } => doctor::doctor(fix)?,
+        Command::Android { command } => android::handle_android(command)?,
+        Command::Completions { shell } => {
+            clap_complete::generate(shell, &mut Cli::command(), "slint-bundler", &mut io::stdout());
         },
-        "android" => android::handle_android(&args),
-        "help" => {
-            if args.len() > 2 {
-                print_command_help(&args[2]);
-            } else {
-                println!("{}", USAGE);
-            }
+        Command::Man { out_dir } => {
+            fs::create_dir_all(&out_dir)?;
+            clap_mangen::generate_to(Cli::command(), &out_dir)?;
+            println!("Wrote man pages to {}", out_dir.display());
         },
-        _ => println!("{}", generate_help_message(&args))
+        Command::Verify { dir, profile, target } => bundle::handle_verify(dir, &profile, target.as_deref())?,
+        Command::Inspect { file } => inspect::inspect(&file)?,
     }
+    Ok(())
 }
-
-fn parse_bundles(args: &[String]) -> Option<Vec<String>> {
-    let bundle_index = args.iter().position(|arg| arg == "--bundles")?;
-    if bundle_index + 1 >= args.len() {
-        return None;
-    }
-    Some(args[bundle_index + 1]
-        .split(',')
-        .map(String::from)
-        .collect())
-}
-
-
-
-fn print_command_help(command: &str) {
-    match command {
-        "build" => println!("Usage: slint-bundler build --bundles <bundle-types>\n\nCreate installation bundles for the project.\nBundle types: deb,msi,rpm,etc\nExample: slint-bundler build --bundles deb,msi"),
-        "dev" => println!("Usage: slint-bundler dev\n\nRun the project in development mode with auto-recompile on file changes."),
-        "icon" => println!("Usage: slint-bundler icon --input <png-file>\n\nGenerate application icons from a 1024x1024 PNG file."),
-        "doctor" => println!("Usage: slint-bundler doctor [--fix]\n\nCheck the development environment setup.\nUse --fix to attempt automatic fixes."),
-        "android" => println!("Usage: slint-bundler android\n\nBuild Android package for the project."),
-        _ => println!("{}", USAGE),
-    }
-}
\ No newline at end of file
@@ -1,13 +1,19 @@
 use std::fs::{self};
 use std::path::{Path, PathBuf};
 use std::io::{self, Write};
+use anyhow::Context as _;
+use crate::error::Result;
 //use crate::icon::generate_pngs;
 
 const ICON: &[u8; 145951] = include_bytes!("./icon.png");
-fn unpack_icon(package_name: &str) {
-    let output_path = format!("{}\\icons\\icon.png", package_name);
-    fs::create_dir_all(format!("{}\\icons", package_name)).expect("Failed to create icons directory");
-    std::fs::write(output_path, ICON).expect("Failed to write icon.png");
+fn unpack_icon(package_name: &str) -> Result<()> {
+    let icons = Path::new(package_name).join("icons");
+    fs::create_dir_all(&icons)
+        .with_context(|| format!("failed to create {}", icons.display()))?;
+    let output_path = icons.join("icon.png");
+    fs::write(&output_path, ICON)
+        .with_context(|| format!("failed to write {}", output_path.display()))?;
+    Ok(())
 }
 
 struct TemplateFile {
@@ -16,63 +22,48 @@ struct TemplateFile {
 }
 
 
-fn get_package_name() -> String {
-    let mut package_name: String = "slint_lib".to_string();
-    print!("Enter your package name(slint_lib):");
-    io::stdout().flush().unwrap();
-    io::stdin().read_line(&mut package_name).expect("Failed to read line");
-    package_name.trim().to_string()
-} 
-fn get_project_name() -> String {
-    let mut project_name: String = "slint-project".to_string();
-    print!("Enter your project name(slint-project):");
-    io::stdout().flush().unwrap();
-    io::stdin().read_line(&mut project_name).expect("Failed to read line");
-    project_name.trim().to_string()
-}
-fn get_app_id() -> String {
-    let mut app_id: String = "io.github.slint.project".to_string();
-    print!("Enter your app id(default: io.github.slint.project):");
-    io::stdout().flush().unwrap();
-    io::stdin().read_line(&mut app_id).expect("Failed to read line");
-    app_id.trim().to_string()
+/// Ask `question` on stdin; an empty answer takes `default`.
+fn prompt(question: &str, default: &str) -> io::Result<String> {
+    print!("{}({}):", question, default);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    let answer = answer.trim();
+    Ok(if answer.is_empty() { default } else { answer }.to_string())
 }
-pub fn handle_new() {
-    let package_name = get_package_name();
-    let app_id = get_app_id();
-    let project_name = get_project_name();
+pub fn handle_new() -> Result<()> {
+    let package_name = prompt("Enter your package name", "slint_lib")?;
+    let app_id = prompt("Enter your app id", "io.github.slint.project")?;
+    let project_name = prompt("Enter your project name", "slint-project")?;
     let version = "0.1.0";
-    let template_files = generate_template_files(&package_name, &version, &app_id, &project_name);
+    let template_files = generate_template_files(&package_name, version, &app_id, &project_name);
     let target_dir = Path::new(&package_name);
     
     for template in template_files {
         let full_path = target_dir.join(&template.path);
         if let Some(parent) = full_path.parent() {
-            fs::create_dir_all(parent).expect("Failed to write template files");
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
         }
-        fs::write(full_path, template.content).expect("Failed to write template files");
+        fs::write(&full_path, template.content)
+            .with_context(|| format!("failed to write {}", full_path.display()))?;
     }
-    unpack_icon(&package_name);
-    
+    unpack_icon(&package_name)?;
+    Ok(())
 }
 fn generate_template_files(package_name: &str, version: &str, app_id: &str, project_name: &str) -> Vec<TemplateFile> {
     let mut files = Vec::new();
-    files.push(TemplateFile {
-        path: PathBuf::from("slint-app.json"),
-        content: format!(r#"{{
-    "app_id": "{app_id}",
-    "project_name": "{project_name}",
-    "version": "{version}",
-    "package_name": "{package_name}" 
-    }}"#, package_name = package_name, version=version, app_id = app_id, project_name = project_name)
-    });
     files.push(TemplateFile {
         path: PathBuf::from("Cargo.toml"),
         content: format!(r#"[package]
 name = "{package_name}"
-version = "0.1.0"
+version = "{version}"
 edition = "2021"
 
+[package.metadata.slint-bundler]
+identifier = "{app_id}"
+display-name = "{project_name}"
+
 [[bin]]
 name = "{package_name}"
 
@@ -94,7 +85,7 @@ slint = "1.14.1"
 [profile.release]
 opt-level = 3
 strip = "debuginfo"
-"#, package_name = package_name)
+"#, package_name = package_name, version = version, app_id = app_id, project_name = project_name)
     });
     files.push(TemplateFile {
         path: PathBuf::from("ui/app-window.slint"),
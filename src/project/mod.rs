@@ -0,0 +1,183 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Workspace layout as reported by `cargo metadata`.
+#[derive(Clone, Debug)]
+pub struct Project {
+    pub workspace_root: PathBuf,
+    pub target_directory: PathBuf,
+    pub packages: Vec<Package>,
+    selected: usize,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Package {
+    pub name: String,
+    pub version: String,
+    pub manifest_path: PathBuf,
+    pub targets: Vec<Target>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Target {
+    pub name: String,
+    pub kind: Vec<String>,
+    pub crate_types: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Metadata {
+    packages: Vec<RawPackage>,
+    workspace_members: Vec<String>,
+    workspace_root: PathBuf,
+    target_directory: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct RawPackage {
+    id: String,
+    #[serde(flatten)]
+    package: Package,
+}
+
+impl Project {
+    /// Run `cargo metadata` for the current directory and select the package it belongs to.
+    pub fn load() -> Result<Project> {
+        let output = Command::new("cargo")
+            .args(["metadata", "--format-version", "1", "--no-deps"])
+            .output()
+            .context("failed to run cargo metadata")?;
+        if !output.status.success() {
+            bail!(
+                "cargo metadata failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        let metadata: Metadata = serde_json::from_slice(&output.stdout)
+            .context("failed to parse cargo metadata output")?;
+
+        let packages: Vec<Package> = metadata
+            .packages
+            .into_iter()
+            .filter(|p| metadata.workspace_members.contains(&p.id))
+            .map(|p| p.package)
+            .collect();
+        if packages.is_empty() {
+            bail!("no packages found in workspace {}", metadata.workspace_root.display());
+        }
+
+        // Prefer the package whose manifest sits in the current directory.
+        let cwd = env::current_dir()?.canonicalize()?;
+        let selected = packages
+            .iter()
+            .position(|p| p.manifest_dir().canonicalize().ok().as_deref() == Some(cwd.as_path()))
+            .unwrap_or(0);
+
+        Ok(Project {
+            workspace_root: metadata.workspace_root,
+            target_directory: metadata.target_directory,
+            packages,
+            selected,
+        })
+    }
+
+    /// The package the current invocation operates on.
+    pub fn package(&self) -> &Package {
+        &self.packages[self.selected]
+    }
+
+    /// Resolve `--package`/`--workspace` to the packages to bundle.
+    pub fn select(&self, names: &[String], workspace: bool) -> Result<Vec<&Package>> {
+        if workspace {
+            let packages: Vec<&Package> = self.packages.iter().filter(|p| p.bins().next().is_some()).collect();
+            if packages.is_empty() {
+                bail!("no workspace member has a binary target");
+            }
+            return Ok(packages);
+        }
+        if names.is_empty() {
+            return Ok(vec![self.package()]);
+        }
+        names
+            .iter()
+            .map(|name| {
+                self.packages
+                    .iter()
+                    .find(|p| &p.name == name)
+                    .with_context(|| format!("package `{}` is not a member of the workspace", name))
+            })
+            .collect()
+    }
+
+    /// Whether the workspace has more than one member, so bundles need a per-package folder.
+    pub fn is_multi_package(&self) -> bool {
+        self.packages.len() > 1
+    }
+
+    /// Directory cargo writes artifacts of `profile` to, e.g. `target/release`
+    /// or `target/aarch64-unknown-linux-gnu/release` when cross-compiling.
+    pub fn artifact_dir(&self, profile: &str, target: Option<&str>) -> PathBuf {
+        let dir = match profile {
+            "dev" | "test" => "debug",
+            "bench" => "release",
+            other => other,
+        };
+        match target {
+            Some(triple) => self.target_directory.join(triple).join(dir),
+            None => self.target_directory.join(dir),
+        }
+    }
+
+    /// Path of a compiled executable for `profile` and an optional target triple.
+    pub fn bin_path(&self, profile: &str, target: Option<&str>, bin: &Target) -> PathBuf {
+        let suffix = match target {
+            Some(triple) if triple.contains("windows") => ".exe",
+            Some(_) => "",
+            None => env::consts::EXE_SUFFIX,
+        };
+        self.artifact_dir(profile, target)
+            .join(format!("{}{}", bin.name, suffix))
+    }
+}
+
+/// Triple of the host toolchain, from `rustc -vV`.
+pub fn host_triple() -> Option<String> {
+    let output = Command::new("rustc").arg("-vV").output().ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix("host: "))
+        .map(str::to_string)
+}
+
+impl Package {
+    pub fn manifest_dir(&self) -> &Path {
+        self.manifest_path.parent().unwrap_or(Path::new("."))
+    }
+
+    pub fn bins(&self) -> impl Iterator<Item = &Target> {
+        self.targets.iter().filter(|t| t.kind.iter().any(|k| k == "bin"))
+    }
+
+    /// The binary that gets bundled: the one named after the package, otherwise the first one.
+    pub fn main_bin(&self) -> Result<&Target> {
+        self.bins()
+            .find(|t| t.name == self.name)
+            .or_else(|| self.bins().next())
+            .with_context(|| format!("package `{}` has no binary target", self.name))
+    }
+
+    pub fn cdylib(&self) -> Option<&Target> {
+        self.targets
+            .iter()
+            .find(|t| t.crate_types.iter().any(|c| c == "cdylib"))
+    }
+
+    /// File name of the shared library Android loads, e.g. `libslinterlib.so`.
+    pub fn cdylib_file_name(&self) -> Option<String> {
+        self.cdylib()
+            .map(|t| format!("lib{}.so", t.name.replace('-', "_")))
+    }
+}
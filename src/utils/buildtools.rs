@@ -1,18 +1,30 @@
-use std::path::Path;
-use std::fs;
+use std::path::PathBuf;
+use std::{env, fs};
+use anyhow::anyhow;
+use crate::error::{Error, Result};
 
-pub fn find_build_tools() -> String {
-    //C:\Users\James\AppData\Local\Android\Sdk\build-tools\36.0.0
-    let username = whoami::username();
-    let path = format!("C:\\Users\\{}\\AppData\\Local\\Android\\Sdk\\build-tools", username);
-    
-        //let mut dir: DirEntry;
-        // for entry in fs::read_dir(".")? {
-        //    let dir = entry?;
-        //     println!("{:?}", dir.path());
-        // }
-    let mut paths = fs::read_dir(&path).unwrap();
-    let relative_path = paths.next().unwrap().expect("Failed to unwrap path").path();
-    let final_path = Path::new(&path).join(relative_path).display().to_string();
-    final_path
-}
\ No newline at end of file
+/// Newest `build-tools/<version>` directory of the Android SDK in `ANDROID_HOME`, or the
+/// Android Studio default location.
+pub fn find_build_tools() -> Result<PathBuf> {
+    let sdk = match env::var_os("ANDROID_HOME") {
+        Some(sdk) => PathBuf::from(sdk),
+        None => {
+            //C:\Users\James\AppData\Local\Android\Sdk
+            let username = whoami::username();
+            PathBuf::from(format!("C:\\Users\\{}\\AppData\\Local\\Android\\Sdk", username))
+        }
+    };
+    let path = sdk.join("build-tools");
+    let entries = fs::read_dir(&path).map_err(|e| {
+        Error::Config(anyhow!("Android build tools not found in {}: {}", path.display(), e))
+    })?;
+    let mut versions = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect::<Vec<_>>();
+    versions.sort();
+    versions
+        .pop()
+        .ok_or_else(|| Error::Config(anyhow!("no Android build tools installed in {}", path.display())))
+}